@@ -0,0 +1,88 @@
+use std::{collections::HashSet, fmt, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{i18n::Msg, tr};
+
+/// Имя учётной записи, созданной из `--auth-token`
+pub const DEFAULT_ADMIN: &str = "admin";
+
+/// Роль админа. Каждая следующая роль может всё, что могут предыдущие
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+  /// Смотрит статистику и список ожидающих
+  Observer,
+  /// Вдобавок отвечает участникам
+  Operator,
+  /// Вдобавок управляет экспериментом и участниками
+  Owner,
+}
+
+impl fmt::Display for Role {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Role::Observer => "observer",
+      Role::Operator => "operator",
+      Role::Owner => "owner",
+    })
+  }
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Account {
+  pub name: String,
+  pub token: String,
+  pub role: Role,
+}
+
+/// Файл учётных записей админов:
+///
+/// ```toml
+/// [[admin]]
+/// name = "alice"
+/// token = "secret"
+/// role = "owner"
+/// ```
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub struct Accounts {
+  #[serde(rename = "admin", default)]
+  pub admins: Vec<Account>,
+}
+
+impl Accounts {
+  /// Единственная учётная запись владельца, как при запуске с одним токеном
+  pub fn single(name: &str, token: String) -> Self {
+    Self {
+      admins: vec![Account {
+        name: name.to_string(),
+        token,
+        role: Role::Owner,
+      }],
+    }
+  }
+
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let data = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let accounts: Self = toml::from_str(&data).map_err(|err| err.to_string())?;
+    accounts.validate()?;
+    Ok(accounts)
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for account in &self.admins {
+      if account.name.is_empty() || account.token.is_empty() {
+        return Err(tr!(Msg::AccountEmpty, account.name));
+      }
+      if !names.insert(&account.name) {
+        return Err(tr!(Msg::AccountDuplicate, account.name));
+      }
+    }
+    Ok(())
+  }
+
+  pub fn find(&self, name: &str) -> Option<&Account> {
+    self.admins.iter().find(|a| a.name == name)
+  }
+}
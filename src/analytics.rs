@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{domain::Domain, history::History, GuessResult};
+
+/// Сводка по тому, как участники угадывают
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub struct Analytics {
+  pub participants: usize,
+  pub solved: usize,
+  /// Попыток до угадывания -> сколько участников угадали за столько
+  pub attempts: BTreeMap<u64, usize>,
+  /// Среднее и медиана попыток среди угадавших
+  pub mean_attempts: Option<f64>,
+  pub median_attempts: Option<f64>,
+  /// Худший случай двоичного поиска по допустимым числам
+  pub optimal_attempts: u32,
+  /// Угадавшие не дольше двоичного поиска
+  pub within_optimal: usize,
+  pub guesses: usize,
+  /// Попытки вне интервала, оставшегося после уже полученных ответов
+  pub outside_interval: usize,
+  /// Первая попытка -> у скольких участников
+  pub first_guesses: BTreeMap<i64, usize>,
+}
+
+impl Analytics {
+  /// Считает сводку по историям участников, `domain` - допустимые числа
+  pub fn new<'a>(domain: &Domain, histories: impl Iterator<Item = &'a History>) -> Self {
+    let mut analytics = Self {
+      optimal_attempts: optimal_attempts(domain.len()),
+      ..Self::default()
+    };
+    let mut solved = Vec::new();
+    for history in histories {
+      analytics.participants += 1;
+      if let Some(first) = history.0.first() {
+        *analytics.first_guesses.entry(first.guess).or_default() += 1;
+      }
+      let mut interval = domain.interval();
+      for (i, entry) in history.0.iter().enumerate() {
+        analytics.guesses += 1;
+        if !interval.contains(entry.guess) {
+          analytics.outside_interval += 1;
+        }
+        match entry.result {
+          Some(GuessResult::Equal) => {
+            solved.push(i as u64 + 1);
+            break;
+          }
+          Some(result) => interval.narrow(entry.guess, result),
+          None => {}
+        }
+      }
+    }
+    solved.sort_unstable();
+    for attempts in &solved {
+      *analytics.attempts.entry(*attempts).or_default() += 1;
+    }
+    analytics.solved = solved.len();
+    analytics.within_optimal = solved
+      .iter()
+      .filter(|attempts| **attempts <= u64::from(analytics.optimal_attempts))
+      .count();
+    if !solved.is_empty() {
+      analytics.mean_attempts = Some(solved.iter().sum::<u64>() as f64 / solved.len() as f64);
+      let mid = solved.len() / 2;
+      analytics.median_attempts = Some(match solved.len() % 2 {
+        0 => (solved[mid - 1] + solved[mid]) as f64 / 2.0,
+        _ => solved[mid] as f64,
+      });
+    }
+    analytics
+  }
+}
+
+/// Двоичный поиск среди `count` чисел угадывает не дольше чем за `⌊log2 count⌋ + 1` попыток
+fn optimal_attempts(count: u128) -> u32 {
+  u128::BITS - count.leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::history::fixtures::{history, DOMAIN};
+
+  #[test]
+  fn no_participants() {
+    let analytics = Analytics::new(&DOMAIN, [].iter());
+    assert_eq!(analytics.participants, 0);
+    assert_eq!(analytics.solved, 0);
+    assert_eq!(analytics.mean_attempts, None);
+    assert_eq!(analytics.median_attempts, None);
+    assert_eq!(analytics.optimal_attempts, 7);
+  }
+
+  #[test]
+  fn empty_and_single_guess() {
+    let histories = [History::default(), history(&[(50, None)])];
+    let analytics = Analytics::new(&DOMAIN, histories.iter());
+    assert_eq!(analytics.participants, 2);
+    assert_eq!(analytics.solved, 0);
+    assert_eq!(analytics.guesses, 1);
+    assert_eq!(analytics.outside_interval, 0);
+    assert_eq!(analytics.first_guesses, BTreeMap::from([(50, 1)]));
+    assert_eq!(analytics.median_attempts, None);
+  }
+
+  #[test]
+  fn mixed() {
+    let histories = [
+      history(&[(50, Some(GuessResult::Equal))]),
+      // Третья попытка уже исключена первым ответом
+      history(&[
+        (50, Some(GuessResult::More)),
+        (75, Some(GuessResult::Less)),
+        (10, Some(GuessResult::More)),
+        (60, Some(GuessResult::Equal)),
+      ]),
+      history(&[(1, Some(GuessResult::More)), (2, None)]),
+    ];
+    let analytics = Analytics::new(&DOMAIN, histories.iter());
+    assert_eq!(analytics.participants, 3);
+    assert_eq!(analytics.solved, 2);
+    assert_eq!(analytics.attempts, BTreeMap::from([(1, 1), (4, 1)]));
+    assert_eq!(analytics.mean_attempts, Some(2.5));
+    assert_eq!(analytics.median_attempts, Some(2.5));
+    assert_eq!(analytics.within_optimal, 2);
+    assert_eq!(analytics.guesses, 7);
+    assert_eq!(analytics.outside_interval, 1);
+    assert_eq!(analytics.first_guesses, BTreeMap::from([(1, 1), (50, 2)]));
+  }
+
+  #[test]
+  fn optimal_attempts_boundaries() {
+    assert_eq!(optimal_attempts(0), 0);
+    assert_eq!(optimal_attempts(1), 1);
+    assert_eq!(optimal_attempts(2), 2);
+    assert_eq!(optimal_attempts(u128::from(u64::MAX) + 1), 65);
+  }
+}
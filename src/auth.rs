@@ -0,0 +1,49 @@
+use std::net::SocketAddr;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// Одноразовое случайное число, которое сервер выдаёт админу для аутентификации
+pub type Nonce = [u8; 32];
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub fn new_nonce() -> Nonce {
+  rand::random()
+}
+
+/// Ответ на вызов: HMAC-SHA256 от `nonce` с ключом `token`
+pub fn sign(token: &str, nonce: &Nonce) -> Vec<u8> {
+  let mut mac = HmacSha256::new_from_slice(token.as_bytes()).unwrap();
+  mac.update(nonce);
+  mac.finalize().into_bytes().to_vec()
+}
+
+/// Проверяет ответ на вызов за время, не зависящее от содержимого ответа
+pub fn verify(token: &str, nonce: &Nonce, response: &[u8]) -> bool {
+  let mut mac = HmacSha256::new_from_slice(token.as_bytes()).unwrap();
+  mac.update(nonce);
+  mac.verify_slice(response).is_ok()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+  /// Неверный ответ на вызов или неизвестное имя админа
+  BadResponse,
+  /// Ответ без выданного вызова
+  NoChallenge,
+  /// Попытка во время блокировки адреса
+  Locked,
+}
+
+/// Запись журнала неудачных попыток аутентификации
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Failure {
+  pub at: DateTime<Utc>,
+  pub address: SocketAddr,
+  pub name: Option<String>,
+  pub reason: FailureReason,
+}
@@ -0,0 +1,70 @@
+use std::{fmt, net::IpAddr, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{i18n::Msg, tr};
+
+/// Кого блокировать: участника или все подключения с адреса
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum BanTarget {
+  Uuid(Uuid),
+  Ip(IpAddr),
+}
+
+impl FromStr for BanTarget {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    if let Ok(uuid) = Uuid::from_str(s) {
+      return Ok(BanTarget::Uuid(uuid));
+    }
+    IpAddr::from_str(s)
+      .map(BanTarget::Ip)
+      .map_err(|_| tr!(Msg::BanTargetInvalid, s))
+  }
+}
+
+impl fmt::Display for BanTarget {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BanTarget::Uuid(uuid) => uuid.fmt(f),
+      BanTarget::Ip(ip) => ip.fmt(f),
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Ban {
+  pub target: BanTarget,
+  pub reason: String,
+  /// Имя админа, который заблокировал
+  pub admin: String,
+  pub at: DateTime<Utc>,
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub struct BanList(pub Vec<Ban>);
+
+impl BanList {
+  /// Блокировка, под которую попадает участник `uuid` с адреса `ip`
+  pub fn find(&self, uuid: Option<Uuid>, ip: IpAddr) -> Option<&Ban> {
+    self.0.iter().find(|ban| match ban.target {
+      BanTarget::Uuid(target) => Some(target) == uuid,
+      BanTarget::Ip(target) => target == ip,
+    })
+  }
+
+  /// Добавляет блокировку, заменяя прежнюю с той же целью
+  pub fn add(&mut self, ban: Ban) {
+    self.remove(ban.target);
+    self.0.push(ban);
+  }
+
+  pub fn remove(&mut self, target: BanTarget) -> bool {
+    let len = self.0.len();
+    self.0.retain(|ban| ban.target != target);
+    self.0.len() != len
+  }
+}
@@ -1,24 +1,34 @@
 use std::{
   io::{self, Write},
   net::{IpAddr, SocketAddr},
+  path::PathBuf,
   process::exit,
   str::FromStr,
-  sync::mpsc,
+  sync::{mpsc, Mutex},
   thread,
+  time::{Duration, Instant},
 };
 
+use chrono::{Days, Local, NaiveTime, Utc};
 use clap::Parser;
-use hogwarts_guess::{AdminToServer, GuessResult, Message, ServerToAdmin};
+use hogwarts_guess::{
+  accounts, auth,
+  bans::BanTarget,
+  heartbeat::{self, Signal},
+  i18n::{self, Msg},
+  leaderboard::{self, Standing},
+  link::{self, Link, Peer},
+  strategy::{Assessment, Strategy},
+  tls, tr, trln, AdminToServer, GuessResult, Message, Profiles, ServerToAdmin, StartAt,
+};
 use message_io::{
-  network::{Endpoint, NetEvent, Transport},
-  node,
+  network::NetEvent,
+  node::{self, NodeEvent, NodeHandler},
 };
 use uuid::Uuid;
 
 #[derive(Parser)]
-#[command(name = "'Хогвартс Лабораторис' админка")]
 #[command(version = "0.1")]
-#[command(about = "Позволяет управлять сервером", long_about = None)]
 struct Cli {
   #[arg(short, long)]
   address: IpAddr,
@@ -26,30 +36,94 @@ struct Cli {
   port: u16,
   #[arg(short = 't', long)]
   auth_token: String,
+  #[arg(short, long, default_value = accounts::DEFAULT_ADMIN)]
+  name: String,
+  #[arg(long)]
+  tls: bool,
+  #[arg(long, requires = "tls")]
+  ca_cert: Option<PathBuf>,
+  #[arg(long, default_value_t = 15)]
+  server_timeout_secs: u64,
+  #[arg(long, default_value_t = 5)]
+  reconnect_attempts: u32,
 }
 
 struct State {
-  local_addr: SocketAddr,
-  server_addr: SocketAddr,
-  endpoint: Endpoint,
+  link: Link,
   auth_token: String,
+  name: String,
+  /// Куда сохранить результаты, когда сервер их пришлёт
+  export_dir: Mutex<PathBuf>,
+}
+
+impl State {
+  fn send(&self, handler: &NodeHandler<Signal>, message: AdminToServer) {
+    self.link.send(handler, &Message::Ats(message));
+  }
+}
+
+impl Peer for State {
+  fn link(&self) -> &Link {
+    &self.link
+  }
+
+  fn reconnecting(&self, attempt: u32, attempts: u32) {
+    trln!(Msg::Reconnecting, attempt, attempts);
+  }
+
+  fn disconnected(&self) {
+    trln!(Msg::ConnectionLost);
+  }
 }
 
 fn main() {
-  let cli = Cli::parse();
+  let cli: Cli = i18n::parse_cli(
+    Msg::AdminName,
+    Msg::AdminAbout,
+    &[
+      ("address", Msg::CliAddress),
+      ("port", Msg::CliPort),
+      ("auth_token", Msg::CliAuthToken),
+      ("name", Msg::CliAdminName),
+      ("tls", Msg::CliTls),
+      ("ca_cert", Msg::CliCaCert),
+      ("server_timeout_secs", Msg::CliServerTimeoutSecs),
+      ("reconnect_attempts", Msg::CliReconnectAttempts),
+    ],
+  );
 
   let server_addr: SocketAddr = (cli.address, cli.port).into();
 
-  let (hnd, listener) = node::split::<()>();
-  let (endpoint, local_addr) = hnd.network().connect(Transport::Tcp, server_addr).unwrap();
+  let (hnd, listener) = node::split::<Signal>();
+
+  let link = match cli.tls {
+    true => tls::client_config(cli.ca_cert.as_deref()).map(Some),
+    false => Ok(None),
+  }
+  .and_then(|tls| {
+    Link::open(
+      server_addr,
+      tls,
+      Duration::from_secs(cli.server_timeout_secs),
+      cli.reconnect_attempts,
+      &hnd,
+    )
+  });
+  let link = match link {
+    Ok(link) => link,
+    Err(err) => {
+      trln!(Msg::TlsConnectFailed, server_addr, err);
+      exit(-1);
+    }
+  };
 
   let (notify, wait) = mpsc::channel::<()>();
 
   let state = &State {
-    local_addr,
-    server_addr,
-    endpoint,
+    link,
     auth_token: cli.auth_token,
+    name: cli.name,
+    export_dir: Mutex::new(PathBuf::from(".")),
   };
 
   thread::scope(|s| {
@@ -65,83 +139,111 @@ fn main() {
 
 fn event_loop(
   state: &State,
-  listener: node::NodeListener<()>,
-  handler: node::NodeHandler<()>,
+  listener: node::NodeListener<Signal>,
+  handler: NodeHandler<Signal>,
   notify: mpsc::Sender<()>,
 ) {
-  listener.for_each(|event| match event.network() {
-    NetEvent::Connected(endpoint, is_ok) => {
-      if is_ok {
-        println!(
-          "Подключено: клиент({}) -> эндпоинт({})",
-          state.local_addr, endpoint
-        );
-        println!("Аутефикация...");
-        let auth = Message::Ats(AdminToServer::Auth(state.auth_token.clone()));
-        handler
-          .network()
-          .send(endpoint, &bincode::serialize(&auth).unwrap());
-      } else {
-        println!(
-          "Не удалось подключить: клиент({}) -> сервер({})",
-          state.local_addr, state.server_addr
-        );
-        handler.stop();
-      }
+  state.link.start_checks(&handler);
+  listener.for_each(|event| match event {
+    NodeEvent::Network(event) => handle_net_event(state, event, &handler, &notify),
+    NodeEvent::Signal(Signal::Heartbeat) => link::check_server(state, &handler),
+    NodeEvent::Signal(Signal::Reconnect) => link::reconnect(state, &handler),
+    NodeEvent::Signal(Signal::Deadlines | Signal::Countdown | Signal::Shutdown | Signal::Stop) => {
+      unreachable!()
+    } // Только у сервера
+  })
+}
+
+fn handle_net_event(
+  state: &State,
+  event: NetEvent,
+  handler: &NodeHandler<Signal>,
+  notify: &mpsc::Sender<()>,
+) {
+  let mut conn = state.link.conn.lock().unwrap();
+  match event {
+    // События прежних соединений уже не важны
+    NetEvent::Connected(endpoint, _)
+    | NetEvent::Message(endpoint, _)
+    | NetEvent::Disconnected(endpoint)
+      if endpoint != conn.endpoint => {}
+    NetEvent::Connected(endpoint, true) => {
+      conn.connected = true;
+      conn.last_seen = Instant::now();
+      trln!(Msg::Connected, conn.local_addr, endpoint);
+      trln!(Msg::Authenticating);
+      drop(conn);
+      // После переподключения авторизуемся заново
+      state.send(
+        handler,
+        AdminToServer::AuthRequest {
+          name: state.name.clone(),
+        },
+      );
+    }
+    NetEvent::Connected(_, false) if conn.attempts > 0 => {
+      drop(conn);
+      handler
+        .signals()
+        .send_with_timer(Signal::Reconnect, heartbeat::RECONNECT_DELAY);
+    }
+    NetEvent::Connected(_, false) => {
+      trln!(Msg::ConnectFailed, conn.local_addr, state.link.server_addr);
+      handler.stop();
     }
     NetEvent::Accepted(_, _) => unreachable!(), // Вызывается только с серверной стороны
-    NetEvent::Message(_, data) => match bincode::deserialize::<Message>(data) {
-      Ok(msg) => {
-        if let Message::Sta(sta) = msg {
-          handle_message(sta, &notify);
-        } else {
-          println!("Невалидная категория сообщения!");
+    NetEvent::Message(endpoint, data) => {
+      // Сервер отвечает - попытки переподключения считаются заново
+      conn.last_seen = Instant::now();
+      conn.attempts = 0;
+      drop(conn);
+      match bincode::deserialize::<Message>(data) {
+        Ok(Message::Sta(sta)) => handle_message(state, sta, handler, notify),
+        Ok(Message::Ping) => {
+          handler
+            .network()
+            .send(endpoint, &bincode::serialize(&Message::Pong).unwrap());
         }
+        Ok(Message::Pong) => {}
+        Ok(_) => trln!(Msg::InvalidCategory),
+        Err(err) => trln!(Msg::ParseFailed, format!("{:?}", err)),
       }
-      Err(err) => println!("Не удалось распарсить сообщение: {:?}", err),
-    },
+    }
     NetEvent::Disconnected(_) => {
-      println!("Подключение потеряно!");
-      handler.stop();
-      exit(-2);
+      drop(conn);
+      link::connection_lost(state, handler);
     }
-  })
+  }
 }
 
-fn handle_input(state: &State, handler: node::NodeHandler<()>, wait: mpsc::Receiver<()>) -> ! {
+fn handle_input(state: &State, handler: NodeHandler<Signal>, wait: mpsc::Receiver<()>) -> ! {
   wait.recv().unwrap();
-  println!(
-    "Добро пожаловать!\
-        \n's' - начать эксперимент\
-        \n'a' - ответить участнику\
-        \n'l' - показать лидерборду\
-        \n'w' - показать ожидающих"
-  );
+  trln!(Msg::AdminWelcome);
   loop {
     let mut inp = String::new();
-    print!("> ");
+    print!("{}", tr!(Msg::Prompt));
     io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut inp).unwrap();
+    if io::stdin().read_line(&mut inp).unwrap() == 0 {
+      handler.stop();
+      exit(0);
+    }
     match inp.trim() {
       "s" => {
-        handler.network().send(
-          state.endpoint,
-          &bincode::serialize(&Message::Ats(AdminToServer::Start)).unwrap(),
-        );
+        state.send(&handler, AdminToServer::Start);
       }
       "a" => {
-        print!("Уид: ");
+        print!("{}", tr!(Msg::UuidPrompt));
         io::stdout().flush().unwrap();
         inp.clear();
         io::stdin().read_line(&mut inp).unwrap();
         let uuid = match Uuid::from_str(inp.trim()) {
           Err(err) => {
-            println!("Ошибка: {}", err);
+            trln!(Msg::Error, err);
             continue;
           }
           Ok(res) => res,
         };
-        print!("Ответ (<, >, =): ");
+        print!("{}", tr!(Msg::AnswerPrompt));
         io::stdout().flush().unwrap();
         inp.clear();
         io::stdin().read_line(&mut inp).unwrap();
@@ -150,64 +252,447 @@ fn handle_input(state: &State, handler: node::NodeHandler<()>, wait: mpsc::Recei
           ">" => GuessResult::More,
           "=" => GuessResult::Equal,
           _ => {
-            println!("Невалидный символ!");
+            trln!(Msg::InvalidSymbol);
             continue;
           }
         };
-        handler.network().send(
-          state.endpoint,
-          &bincode::serialize(&Message::Ats(AdminToServer::SendAnswer {
+        state.send(
+          &handler,
+          AdminToServer::SendAnswer {
             target: uuid,
             answer: ans,
-          }))
-          .unwrap(),
+          },
         );
       }
       "l" => {
-        handler.network().send(
-          state.endpoint,
-          &bincode::serialize(&Message::Ats(AdminToServer::Stats)).unwrap(),
-        );
+        state.send(&handler, AdminToServer::Stats);
       }
       "w" => {
-        handler.network().send(
-          state.endpoint,
-          &bincode::serialize(&Message::Ats(AdminToServer::WaitAnswers)).unwrap(),
-        );
+        state.send(&handler, AdminToServer::WaitAnswers);
+      }
+      "f" => {
+        state.send(&handler, AdminToServer::AuthFailures);
       }
-      _ => println!("Некорректная комманда!"),
+      "i" => {
+        print!("{}", tr!(Msg::InviteLabelPrompt));
+        io::stdout().flush().unwrap();
+        inp.clear();
+        io::stdin().read_line(&mut inp).unwrap();
+        let label = inp.trim().to_string();
+        print!("{}", tr!(Msg::InviteUsesPrompt));
+        io::stdout().flush().unwrap();
+        inp.clear();
+        io::stdin().read_line(&mut inp).unwrap();
+        let uses = match inp.trim() {
+          "" => 1,
+          uses => match uses.parse::<u32>() {
+            Err(err) => {
+              trln!(Msg::Error, err);
+              continue;
+            }
+            Ok(uses) => uses,
+          },
+        };
+        state.send(&handler, AdminToServer::CreateInvite { label, uses });
+      }
+      "k" => {
+        print!("{}", tr!(Msg::UuidPrompt));
+        io::stdout().flush().unwrap();
+        inp.clear();
+        io::stdin().read_line(&mut inp).unwrap();
+        let target = match Uuid::from_str(inp.trim()) {
+          Err(err) => {
+            trln!(Msg::Error, err);
+            continue;
+          }
+          Ok(res) => res,
+        };
+        let reason = read_reason();
+        state.send(&handler, AdminToServer::Kick { target, reason });
+      }
+      "b" | "u" => {
+        print!("{}", tr!(Msg::BanTargetPrompt));
+        io::stdout().flush().unwrap();
+        let command = inp.trim().to_string();
+        inp.clear();
+        io::stdin().read_line(&mut inp).unwrap();
+        let target = match BanTarget::from_str(inp.trim()) {
+          Err(err) => {
+            trln!(Msg::Error, err);
+            continue;
+          }
+          Ok(res) => res,
+        };
+        let message = match command.as_str() {
+          "b" => AdminToServer::Ban {
+            target,
+            reason: read_reason(),
+          },
+          _ => AdminToServer::Unban(target),
+        };
+        state.send(&handler, message);
+      }
+      "n" => {
+        state.send(&handler, AdminToServer::Bans);
+      }
+      "v" => {
+        state.send(&handler, AdminToServer::Invites);
+      }
+      "p" => state.send(&handler, AdminToServer::PublishLeaderboard),
+      "r" => state.send(&handler, AdminToServer::Analytics),
+      "e" => {
+        print!("{}", tr!(Msg::ExportDirPrompt));
+        io::stdout().flush().unwrap();
+        inp.clear();
+        io::stdin().read_line(&mut inp).unwrap();
+        let dir = match inp.trim() {
+          "" => ".",
+          dir => dir,
+        };
+        *state.export_dir.lock().unwrap() = PathBuf::from(dir);
+        state.send(&handler, AdminToServer::Export);
+      }
+      "d" => {
+        print!("{}", tr!(Msg::StartAtPrompt));
+        io::stdout().flush().unwrap();
+        inp.clear();
+        io::stdin().read_line(&mut inp).unwrap();
+        let at = match parse_start_at(inp.trim()) {
+          Ok(at) => at,
+          Err(err) => {
+            trln!(Msg::Error, err);
+            continue;
+          }
+        };
+        print!("{}", tr!(Msg::ReadyCheckConfirm));
+        io::stdout().flush().unwrap();
+        inp.clear();
+        io::stdin().read_line(&mut inp).unwrap();
+        let ready_check = inp.trim() == "y";
+        state.send(&handler, AdminToServer::ScheduleStart { at, ready_check });
+      }
+      "c" => state.send(&handler, AdminToServer::CancelStart),
+      "q" => state.send(&handler, AdminToServer::LateQueue),
+      "j" => state.send(&handler, AdminToServer::AdmitLate),
+      "z" => {
+        print!("{}", tr!(Msg::UuidsPrompt));
+        io::stdout().flush().unwrap();
+        inp.clear();
+        io::stdin().read_line(&mut inp).unwrap();
+        let targets: Result<Vec<Uuid>, _> = inp.split_whitespace().map(Uuid::from_str).collect();
+        match targets {
+          Ok(targets) => state.send(&handler, AdminToServer::Restart { targets }),
+          Err(err) => trln!(Msg::Error, err),
+        }
+      }
+      "x" => {
+        print!("{}", tr!(Msg::ShutdownConfirm));
+        io::stdout().flush().unwrap();
+        inp.clear();
+        io::stdin().read_line(&mut inp).unwrap();
+        if inp.trim() == "y" {
+          state.send(&handler, AdminToServer::Shutdown);
+        }
+      }
+      _ => trln!(Msg::InvalidCommand),
     }
   }
 }
 
-fn handle_message(message: ServerToAdmin, notify: &mpsc::Sender<()>) {
+fn handle_message(
+  state: &State,
+  message: ServerToAdmin,
+  handler: &NodeHandler<Signal>,
+  notify: &mpsc::Sender<()>,
+) {
   match message {
-    ServerToAdmin::Stats(stats) => {
-      let mut vec: Vec<_> = stats.0.into_iter().collect();
-      vec.sort_unstable_by_key(|e| e.1);
-      println!("\nСтатистика (уид, количество):");
-      for (uuid, count) in vec.iter().rev() {
-        println!("{} :: {}", uuid, count);
+    ServerToAdmin::Leaderboard(leaderboard) => {
+      trln!(Msg::LeaderboardHeader, leaderboard.describe());
+      let uuid = |s: &Standing| s.uuid.map_or(String::new(), |uuid| uuid.to_string());
+      trln!(Msg::AdminSolvedHeader);
+      for standing in &leaderboard.solved {
+        println!(
+          "{}. {} :: {} :: {} :: {} :: {}",
+          standing.rank.unwrap_or(0),
+          uuid(standing),
+          standing.name,
+          standing.guesses,
+          leaderboard::clock(standing.time_to_solve),
+          assessment_name(standing.assessment)
+        );
+      }
+      trln!(Msg::AdminUnsolvedHeader);
+      for standing in &leaderboard.unsolved {
+        println!(
+          "{} :: {} :: {} :: {} :: {}",
+          uuid(standing),
+          standing.name,
+          standing.guesses,
+          leaderboard::outcome(standing.outcome),
+          assessment_name(standing.assessment)
+        );
+      }
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Analytics(analytics) => {
+      let average = |value: Option<f64>| value.map_or("-".to_string(), |v| format!("{:.1}", v));
+      trln!(Msg::AnalyticsHeader);
+      trln!(
+        Msg::AnalyticsSolved,
+        analytics.solved,
+        analytics.participants
+      );
+      trln!(
+        Msg::AnalyticsAttempts,
+        average(analytics.mean_attempts),
+        average(analytics.median_attempts)
+      );
+      trln!(
+        Msg::AnalyticsOptimal,
+        analytics.optimal_attempts,
+        analytics.within_optimal
+      );
+      trln!(Msg::AnalyticsHistogram);
+      for (attempts, count) in &analytics.attempts {
+        println!("{:>4}: {} {}", attempts, "#".repeat(*count), count);
+      }
+      trln!(
+        Msg::AnalyticsOutside,
+        analytics.outside_interval,
+        analytics.guesses
+      );
+      trln!(Msg::AnalyticsFirstGuesses);
+      for (guess, count) in &analytics.first_guesses {
+        println!("{}: {}", guess, count);
+      }
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Lobby { ready, total } => {
+      trln!(Msg::LobbyStatus, ready, total);
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::StartScheduled(at) => {
+      trln!(
+        Msg::StartScheduledAt,
+        at.with_timezone(&Local).format("%H:%M:%S")
+      );
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::StartCancelled => {
+      trln!(Msg::StartCancelled);
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::LateJoin { uuid, name, queued } => {
+      match queued {
+        true => trln!(Msg::LateJoinQueued, name, uuid),
+        false => trln!(Msg::LateJoinStarted, name, uuid),
       }
-      print!("> ");
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::LateQueue(queue, profiles) => {
+      trln!(Msg::LateQueueHeader);
+      for uuid in queue {
+        println!("{} :: {}", uuid, display_name(&profiles, &uuid));
+      }
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Admitted(count) => {
+      trln!(Msg::LateAdmitted, count);
+      print!("{}", tr!(Msg::Prompt));
       io::stdout().flush().unwrap();
     }
-    ServerToAdmin::WaitAnswers(wait_answers) => {
-      println!("\nОжидающие ответа (уид, предположение)");
+    ServerToAdmin::InvalidStartTime => {
+      trln!(Msg::InvalidStartTime);
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::AlreadyStarted => {
+      trln!(Msg::AlreadyStarted);
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Restarted(count) => {
+      trln!(Msg::RestartedCount, count);
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::LeaderboardPublished(count) => {
+      trln!(Msg::LeaderboardPublished, count);
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::WaitAnswers(wait_answers, profiles) => {
+      trln!(Msg::WaitHeader);
       for (uuid, guess) in wait_answers.0 {
-        println!("{} :: {}", uuid, guess);
+        println!(
+          "{} :: {} :: {}",
+          uuid,
+          display_name(&profiles, &uuid),
+          guess
+        );
       }
-      print!("> ");
+      print!("{}", tr!(Msg::Prompt));
       io::stdout().flush().unwrap();
     }
-    ServerToAdmin::ResultAuth(ack) => {
-      if ack {
-        println!("Аутефикация успешна!");
+    ServerToAdmin::Experiment(rules) => {
+      for line in rules.describe() {
+        println!("{}", line);
+      }
+    }
+    ServerToAdmin::Challenge(nonce) => {
+      let response = auth::sign(&state.auth_token, &nonce);
+      state.send(handler, AdminToServer::Auth(response));
+    }
+    ServerToAdmin::ResultAuth(role) => {
+      if let Some(role) = role {
+        trln!(Msg::AuthSucceeded, role);
         notify.send(()).unwrap();
       } else {
-        println!("Аутефикация неуспешна!");
+        trln!(Msg::AuthFailed);
         exit(-1);
       }
     }
+    ServerToAdmin::PermissionDenied(role) => {
+      trln!(Msg::PermissionDenied, role);
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::InviteCreated(invite) => {
+      trln!(Msg::InviteCreated, invite.code, invite.label, invite.uses);
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Invites(invites) => {
+      trln!(Msg::InvitesHeader);
+      for invite in invites {
+        println!("{} :: {} :: {}", invite.code, invite.label, invite.uses);
+      }
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Kicked(count) => {
+      trln!(Msg::KickedCount, count);
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Unbanned(removed) => {
+      match removed {
+        true => trln!(Msg::Unbanned),
+        false => trln!(Msg::UnbanMissing),
+      }
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Bans(bans) => {
+      trln!(Msg::BansHeader);
+      for ban in bans {
+        println!(
+          "{} :: {} :: {} :: {}",
+          ban.target,
+          ban.reason,
+          ban.admin,
+          ban.at.with_timezone(&chrono::Local).format("%H:%M:%S")
+        );
+      }
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Export(export) => {
+      match export.write(&state.export_dir.lock().unwrap()) {
+        Ok(paths) => {
+          let paths: Vec<_> = paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+          trln!(Msg::ExportSaved, paths.join(", "));
+        }
+        Err(err) => trln!(Msg::ExportFailed, err),
+      }
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+    ServerToAdmin::Shutdown => {
+      trln!(Msg::ServerShutdown);
+      handler.stop();
+      exit(0);
+    }
+    ServerToAdmin::AuthLocked(secs) => {
+      trln!(Msg::AuthLocked, secs);
+      exit(-1);
+    }
+    ServerToAdmin::AuthFailures(failures) => {
+      trln!(Msg::AuthFailuresHeader);
+      for failure in failures {
+        trln!(
+          Msg::AuthFailureLine,
+          failure.at.with_timezone(&chrono::Local).format("%H:%M:%S"),
+          failure.address,
+          failure.name.as_deref().unwrap_or("-"),
+          format!("{:?}", failure.reason)
+        );
+      }
+      print!("{}", tr!(Msg::Prompt));
+      io::stdout().flush().unwrap();
+    }
+  }
+}
+
+fn read_reason() -> String {
+  print!("{}", tr!(Msg::ReasonPrompt));
+  io::stdout().flush().unwrap();
+  let mut inp = String::new();
+  io::stdin().read_line(&mut inp).unwrap();
+  inp.trim().to_string()
+}
+
+/// Дальше недели вперёд старт назначают разве что по ошибке
+const MAX_START_DELAY_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Секунды до старта или `ЧЧ:ММ[:СС]` по местному времени, ближайшее в будущем
+fn parse_start_at(text: &str) -> Result<StartAt, String> {
+  if let Ok(secs) = text.parse::<u64>() {
+    if secs > MAX_START_DELAY_SECS {
+      return Err(tr!(Msg::StartDelayTooLong, MAX_START_DELAY_SECS));
+    }
+    return Ok(StartAt::After(secs));
+  }
+  let time = NaiveTime::parse_from_str(text, "%H:%M:%S")
+    .or_else(|_| NaiveTime::parse_from_str(text, "%H:%M"))
+    .map_err(|err| err.to_string())?;
+  let now = Local::now();
+  let mut at = now.date_naive().and_time(time);
+  if at <= now.naive_local() {
+    at = at + Days::new(1);
   }
+  at.and_local_timezone(Local)
+    .earliest()
+    .map(|at| StartAt::At(at.with_timezone(&Utc)))
+    .ok_or_else(|| tr!(Msg::StartTimeSkipped, text))
+}
+
+/// Стратегия с эффективностью, прочерк без оценки
+fn assessment_name(assessment: Option<Assessment>) -> String {
+  let Some(assessment) = assessment else {
+    return "-".to_string();
+  };
+  let name = i18n::text(match assessment.strategy {
+    Strategy::Binary => Msg::StrategyBinary,
+    Strategy::Linear => Msg::StrategyLinear,
+    Strategy::Random => Msg::StrategyRandom,
+  });
+  format!("{} ({}%)", name, assessment.efficiency)
+}
+
+fn display_name(profiles: &Profiles, uuid: &Uuid) -> String {
+  profiles
+    .0
+    .get(uuid)
+    .map_or_else(|| "-".to_string(), |profile| profile.to_string())
 }
@@ -1,51 +1,426 @@
-#![feature(iter_intersperse)]
-
 use std::{
-  io::{self, Write},
+  io::{self, BufRead, Write},
   net::{IpAddr, SocketAddr},
+  path::PathBuf,
   process::exit,
-  sync::mpsc,
+  sync::{mpsc, Mutex},
   thread,
+  time::{Duration, Instant},
 };
 
+use chrono::Local;
 use clap::Parser;
-use hogwarts_guess::{ClientToServer, GuessResult, Message, ServerToClient};
+use hogwarts_guess::{
+  domain::Domain,
+  heartbeat::{self, Signal},
+  history::{History, HistoryEntry, Interval},
+  i18n::{self, Msg},
+  leaderboard::{self, Leaderboard, Standing},
+  link::{self, Link, Peer},
+  profile::{self, Profile},
+  rules::Rules,
+  tls, tr, trln, ClientToServer, GuessResult, Message, RejectReason, ServerToClient,
+};
 use message_io::{
-  network::{Endpoint, NetEvent, Transport},
-  node::{self, NodeHandler},
+  network::NetEvent,
+  node::{self, NodeEvent, NodeHandler},
 };
+use serde::Serialize;
+use uuid::Uuid;
 
 #[derive(Parser)]
-#[command(name = "'Хогвартс Лабораторис' клиент")]
 #[command(version = "0.1")]
-#[command(about = "Позволяет участвовать в эксперименте о угадывании чисел", long_about = None)]
 struct Cli {
   #[arg(short, long)]
   address: IpAddr,
   #[arg(short, long, default_value_t = 6969)]
   port: u16,
+  #[arg(long, default_value = ".hogwarts_history")]
+  history_dir: PathBuf,
+  #[arg(short, long)]
+  resume: Option<Uuid>,
+  #[arg(short, long, conflicts_with = "resume")]
+  invite: Option<String>,
+  #[arg(short = 'n', long = "name")]
+  display_name: Option<String>,
+  #[arg(short, long)]
+  group: Option<String>,
+  #[arg(long)]
+  student_id: Option<String>,
+  #[arg(long, conflicts_with = "guesses")]
+  stdin_script: bool,
+  #[arg(long, value_delimiter = ',', num_args = 1.., allow_negative_numbers = true)]
+  guesses: Option<Vec<i64>>,
+  #[arg(long)]
+  tls: bool,
+  #[arg(long, requires = "tls")]
+  ca_cert: Option<PathBuf>,
+  #[arg(long, default_value_t = 15)]
+  server_timeout_secs: u64,
+  #[arg(long, default_value_t = 5)]
+  reconnect_attempts: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+  Interactive,
+  Script,
+  Guesses,
 }
 
 struct State {
-  local_addr: SocketAddr,
-  server_addr: SocketAddr,
-  endpoint: Endpoint,
+  link: Link,
+  mode: Mode,
+  history_dir: PathBuf,
+  resume: Option<Uuid>,
+  invite: Option<String>,
+  profile: Profile,
+  session: Mutex<Session>,
+}
+
+/// Данные участника, известные после регистрации
+#[derive(Default)]
+struct Session {
+  uuid: Option<Uuid>,
+  history: History,
+  /// Условия эксперимента, сообщаются в его начале
+  rules: Rules,
+  /// Старт уже получен: после переподключения сервер присылает его снова
+  started: bool,
+}
+
+/// Что поток ввода узнаёт от сетевого потока
+enum Notice {
+  /// Админ проверяет готовность
+  ReadyCheck,
+  Start,
+  /// Админ начал заново: прежние попытки не в счёт
+  Restart,
+  Answer(GuessResult),
+  /// Сервер не принял предположение
+  Rejected,
+  /// Участник выбыл, не угадав
+  Out,
+}
+
+/// События клиента. В машинном режиме каждое выводится одной строкой JSON
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+  Connected {
+    local_addr: SocketAddr,
+    server_addr: SocketAddr,
+  },
+  ConnectFailed {
+    local_addr: SocketAddr,
+    server_addr: SocketAddr,
+  },
+  Registered {
+    uuid: Uuid,
+    history: Option<usize>,
+  },
+  Rejected {
+    reason: RejectReason,
+  },
+  Kicked {
+    reason: String,
+    banned: bool,
+  },
+  ReadyCheck,
+  Countdown {
+    seconds: u64,
+  },
+  StartCancelled,
+  LateQueued,
+  NotStarted,
+  Start {
+    rules: &'a Rules,
+  },
+  Restart {
+    rules: &'a Rules,
+  },
+  Answer {
+    guess: Option<i64>,
+    result: GuessResult,
+    interval: Interval,
+  },
+  History {
+    entries: &'a [HistoryEntry],
+    interval: Interval,
+  },
+  GuessRejected {
+    guess: i64,
+    domain: &'a Domain,
+  },
+  OutOfAttempts,
+  TimeUp,
+  Finished {
+    solved: bool,
+  },
+  Leaderboard {
+    leaderboard: Leaderboard,
+  },
+  Error {
+    message: String,
+  },
+  Reconnecting {
+    attempt: u32,
+    attempts: u32,
+  },
+  Shutdown,
+  Disconnected,
+}
+
+impl State {
+  fn send(&self, handler: &NodeHandler<Signal>, message: &Message) {
+    self.link.send(handler, message);
+  }
+
+  fn save_history(&self, session: &Session) {
+    let Some(uuid) = session.uuid else {
+      return;
+    };
+    let path = self.history_dir.join(format!("{}.bin", uuid));
+    if let Err(err) = session.history.save(&path) {
+      self.report(Event::Error {
+        message: tr!(Msg::HistorySaveFailed, err),
+      });
+    }
+  }
+
+  /// Убирает из истории попытку без ответа: сервер её не принял
+  fn discard_pending(&self, session: &mut Session) {
+    if session
+      .history
+      .0
+      .last()
+      .is_some_and(|entry| entry.result.is_none())
+    {
+      session.history.0.pop();
+      self.save_history(session);
+    }
+  }
+
+  fn report(&self, event: Event) {
+    if self.mode != Mode::Interactive {
+      return println!("{}", serde_json::to_string(&event).unwrap());
+    }
+    match event {
+      Event::Connected {
+        local_addr,
+        server_addr,
+      } => {
+        trln!(Msg::Connected, local_addr, server_addr);
+        trln!(Msg::Authenticating);
+      }
+      Event::ConnectFailed {
+        local_addr,
+        server_addr,
+      } => trln!(Msg::ConnectFailed, local_addr, server_addr),
+      Event::Registered { uuid, history } => {
+        trln!(Msg::ParticipantToken, uuid);
+        if let Some(len) = history {
+          trln!(Msg::HistoryLoaded, len);
+        }
+      }
+      Event::Rejected { reason } => match reason {
+        RejectReason::Invite => trln!(Msg::RegisterRejected),
+        RejectReason::Profile => trln!(Msg::ProfileRejected, profile::FIELD_MAX_LEN),
+      },
+      Event::Kicked { reason, banned } => match banned {
+        true => trln!(Msg::Banned, reason),
+        false => trln!(Msg::Kicked, reason),
+      },
+      Event::ReadyCheck => trln!(Msg::ReadyCheckPrompt),
+      // Поначалу раз в десять секунд, под конец - каждую
+      Event::Countdown { seconds } if seconds <= 10 || seconds % 10 == 0 => {
+        trln!(Msg::CountdownTick, seconds)
+      }
+      Event::Countdown { .. } => {}
+      Event::StartCancelled => trln!(Msg::StartCancelled),
+      Event::LateQueued => trln!(Msg::LateQueued),
+      Event::NotStarted => trln!(Msg::NotStarted),
+      Event::Start { rules } => {
+        trln!(Msg::ExperimentStarted);
+        for line in rules.describe() {
+          println!("{}", line);
+        }
+      }
+      Event::Restart { rules } => {
+        trln!(Msg::ExperimentRestarted);
+        for line in rules.describe() {
+          println!("{}", line);
+        }
+      }
+      Event::Answer {
+        guess,
+        result,
+        interval,
+      } => {
+        println!();
+        match guess {
+          Some(guess) => trln!(Msg::AnswerFor, guess, result_name(result)),
+          None => trln!(Msg::Answer, result_name(result)),
+        }
+        trln!(Msg::IntervalLine, interval);
+        print!("{}", tr!(Msg::Prompt));
+        io::stdout().flush().unwrap();
+      }
+      Event::History { entries, interval } => {
+        trln!(Msg::HistoryHeader);
+        for entry in entries {
+          let result = entry
+            .result
+            .map_or(i18n::text(Msg::AwaitingAnswer), result_name);
+          trln!(
+            Msg::HistoryLine,
+            entry.guessed_at.with_timezone(&Local).format("%H:%M:%S"),
+            entry.guess,
+            result
+          );
+        }
+        trln!(Msg::IntervalLine, interval);
+      }
+      Event::GuessRejected { guess, domain } => {
+        trln!(Msg::GuessRejected, guess, domain.describe());
+        print!("{}", tr!(Msg::Prompt));
+        io::stdout().flush().unwrap();
+      }
+      Event::OutOfAttempts => trln!(Msg::OutOfAttempts),
+      Event::TimeUp => trln!(Msg::TimeUp),
+      Event::Finished { solved } => match solved {
+        true => trln!(Msg::Solved),
+        false => trln!(Msg::GuessesExhausted),
+      },
+      Event::Leaderboard { leaderboard } => {
+        let you = |s: &Standing| match s.uuid {
+          Some(_) => format!(" {}", tr!(Msg::LeaderboardYou)),
+          None => String::new(),
+        };
+        println!();
+        trln!(Msg::LeaderboardHeader, leaderboard.describe());
+        trln!(Msg::SolvedHeader);
+        for standing in &leaderboard.solved {
+          println!(
+            "{}. {} :: {} :: {}{}",
+            standing.rank.unwrap_or(0),
+            standing.name,
+            standing.guesses,
+            leaderboard::clock(standing.time_to_solve),
+            you(standing)
+          );
+        }
+        trln!(Msg::UnsolvedHeader);
+        for standing in &leaderboard.unsolved {
+          println!(
+            "{} :: {} :: {}{}",
+            standing.name,
+            standing.guesses,
+            leaderboard::outcome(standing.outcome),
+            you(standing)
+          );
+        }
+        print!("{}", tr!(Msg::Prompt));
+        io::stdout().flush().unwrap();
+      }
+      Event::Error { message } => println!("{}", message),
+      Event::Reconnecting { attempt, attempts } => trln!(Msg::Reconnecting, attempt, attempts),
+      Event::Shutdown => trln!(Msg::ServerShutdown),
+      Event::Disconnected => trln!(Msg::ConnectionLost),
+    }
+  }
+}
+
+impl Peer for State {
+  fn link(&self) -> &Link {
+    &self.link
+  }
+
+  fn reconnecting(&self, attempt: u32, attempts: u32) {
+    self.report(Event::Reconnecting { attempt, attempts });
+  }
+
+  fn disconnected(&self) {
+    self.report(Event::Disconnected);
+  }
 }
 
 fn main() {
-  let cli = Cli::parse();
+  let cli: Cli = i18n::parse_cli(
+    Msg::ClientName,
+    Msg::ClientAbout,
+    &[
+      ("address", Msg::CliAddress),
+      ("port", Msg::CliPort),
+      ("history_dir", Msg::CliHistoryDir),
+      ("resume", Msg::CliResume),
+      ("invite", Msg::CliInvite),
+      ("display_name", Msg::CliDisplayName),
+      ("group", Msg::CliGroup),
+      ("student_id", Msg::CliStudentId),
+      ("stdin_script", Msg::CliStdinScript),
+      ("guesses", Msg::CliGuesses),
+      ("tls", Msg::CliTls),
+      ("ca_cert", Msg::CliCaCert),
+      ("server_timeout_secs", Msg::CliServerTimeoutSecs),
+      ("reconnect_attempts", Msg::CliReconnectAttempts),
+    ],
+  );
 
   let server_addr: SocketAddr = (cli.address, cli.port).into();
 
-  let (hnd, listener) = node::split::<()>();
-  let (endpoint, local_addr) = hnd.network().connect(Transport::Tcp, server_addr).unwrap();
+  let mode = match (cli.stdin_script, &cli.guesses) {
+    (true, _) => Mode::Script,
+    (false, Some(_)) => Mode::Guesses,
+    (false, None) => Mode::Interactive,
+  };
+
+  let (hnd, listener) = node::split::<Signal>();
+
+  let link = match cli.tls {
+    true => tls::client_config(cli.ca_cert.as_deref()).map(Some),
+    false => Ok(None),
+  }
+  .and_then(|tls| {
+    Link::open(
+      server_addr,
+      tls,
+      Duration::from_secs(cli.server_timeout_secs),
+      cli.reconnect_attempts,
+      &hnd,
+    )
+  });
+  let link = match link {
+    Ok(link) => link,
+    Err(err) => {
+      let message = tr!(Msg::TlsConnectFailed, server_addr, err);
+      match mode {
+        Mode::Interactive => println!("{}", message),
+        _ => println!(
+          "{}",
+          serde_json::to_string(&Event::Error { message }).unwrap()
+        ),
+      }
+      exit(-1);
+    }
+  };
 
-  let (notify, wait) = mpsc::channel::<()>();
+  // Сетевой поток сообщает потоку ввода то, чего тот ждёт. Поток ввода может
+  // закончить работу раньше, поэтому ошибки отправки не важны
+  let (notify, wait) = mpsc::channel::<Notice>();
 
   let state = &State {
-    endpoint,
-    local_addr,
-    server_addr,
+    link,
+    mode,
+    history_dir: cli.history_dir,
+    resume: cli.resume,
+    invite: cli.invite,
+    profile: Profile {
+      name: cli.display_name,
+      group: cli.group,
+      student_id: cli.student_id,
+    },
+    session: Mutex::new(Session::default()),
   };
 
   thread::scope(|s| {
@@ -55,139 +430,386 @@ fn main() {
     });
 
     let handler = hnd.clone();
-    s.spawn(move || handle_input(state, handler, wait));
+    match cli.guesses {
+      Some(guesses) if mode == Mode::Guesses => {
+        s.spawn(move || run_guesses(state, handler, wait, guesses));
+      }
+      _ if mode == Mode::Script => {
+        s.spawn(move || run_script(state, handler, wait));
+      }
+      _ => {
+        s.spawn(move || handle_input(state, handler, wait));
+      }
+    }
   });
 }
 
 fn event_loop(
   state: &State,
-  listener: node::NodeListener<()>,
-  handler: NodeHandler<()>,
-  notify: mpsc::Sender<()>,
+  listener: node::NodeListener<Signal>,
+  handler: NodeHandler<Signal>,
+  notify: mpsc::Sender<Notice>,
 ) {
-  listener.for_each(|event| match event.network() {
-    NetEvent::Connected(endpoint, is_ok) => {
-      if is_ok {
-        println!(
-          "Подключено: клиент({}) -> эндпоинт({})",
-          state.local_addr, state.endpoint
-        );
-        println!("Аутефикация...");
-        let auth = Message::Cts(ClientToServer::Register);
-        handler
-          .network()
-          .send(endpoint, &bincode::serialize(&auth).unwrap());
-      } else {
-        println!(
-          "Не удалось подключить: клиент({}) -> сервер({})",
-          state.local_addr, state.server_addr
-        );
-        handler.stop();
-      }
+  state.link.start_checks(&handler);
+  listener.for_each(|event| match event {
+    NodeEvent::Network(event) => handle_net_event(state, event, &handler, &notify),
+    NodeEvent::Signal(Signal::Heartbeat) => link::check_server(state, &handler),
+    NodeEvent::Signal(Signal::Reconnect) => link::reconnect(state, &handler),
+    NodeEvent::Signal(Signal::Deadlines | Signal::Countdown | Signal::Shutdown | Signal::Stop) => {
+      unreachable!()
+    } // Только у сервера
+  });
+}
+
+fn handle_net_event(
+  state: &State,
+  event: NetEvent,
+  handler: &NodeHandler<Signal>,
+  notify: &mpsc::Sender<Notice>,
+) {
+  let mut conn = state.link.conn.lock().unwrap();
+  match event {
+    // События прежних соединений уже не важны
+    NetEvent::Connected(endpoint, _)
+    | NetEvent::Message(endpoint, _)
+    | NetEvent::Disconnected(endpoint)
+      if endpoint != conn.endpoint => {}
+    NetEvent::Connected(_, true) => {
+      conn.connected = true;
+      conn.last_seen = Instant::now();
+      state.report(Event::Connected {
+        local_addr: conn.local_addr,
+        server_addr: state.link.server_addr,
+      });
+      drop(conn);
+      // После переподключения продолжаем под уже выданным токеном
+      let uuid = state.session.lock().unwrap().uuid.or(state.resume);
+      let auth = match uuid {
+        Some(uuid) => Message::Cts(ClientToServer::Resume {
+          uuid,
+          profile: state.profile.clone(),
+        }),
+        None => Message::Cts(ClientToServer::Register {
+          invite: state.invite.clone(),
+          profile: state.profile.clone(),
+        }),
+      };
+      state.send(handler, &auth);
+    }
+    NetEvent::Connected(_, false) if conn.attempts > 0 => {
+      drop(conn);
+      handler
+        .signals()
+        .send_with_timer(Signal::Reconnect, heartbeat::RECONNECT_DELAY);
+    }
+    NetEvent::Connected(_, false) => {
+      state.report(Event::ConnectFailed {
+        local_addr: conn.local_addr,
+        server_addr: state.link.server_addr,
+      });
+      handler.stop();
     }
     NetEvent::Accepted(_, _) => unreachable!(), // Вызывается только с серверной стороны
-    NetEvent::Message(_, data) => match bincode::deserialize::<Message>(data) {
-      Ok(msg) => {
-        if let Message::Stc(sta) = msg {
-          handle_message(state, sta, &handler, &notify);
-        } else {
-          println!("Невалидная категория сообщения!");
-        }
+    NetEvent::Message(_, data) => {
+      // Сервер отвечает - попытки переподключения считаются заново
+      conn.last_seen = Instant::now();
+      conn.attempts = 0;
+      drop(conn);
+      match bincode::deserialize::<Message>(data) {
+        Ok(Message::Stc(sta)) => handle_message(state, sta, handler, notify),
+        Ok(Message::Ping) => state.send(handler, &Message::Pong),
+        Ok(Message::Pong) => {}
+        Ok(_) => state.report(Event::Error {
+          message: tr!(Msg::InvalidCategory),
+        }),
+        Err(err) => state.report(Event::Error {
+          message: tr!(Msg::ParseFailed, format!("{:?}", err)),
+        }),
       }
-      Err(err) => println!("Не удалось распарсить сообщение: {:?}", err),
-    },
+    }
     NetEvent::Disconnected(_) => {
-      println!("Подключение потеряно!");
-      handler.stop();
-      exit(-2);
+      drop(conn);
+      link::connection_lost(state, handler);
     }
-  });
+  }
 }
 
 fn handle_message(
   state: &State,
   message: ServerToClient,
-  handler: &NodeHandler<()>,
-  notify: &mpsc::Sender<()>,
+  handler: &NodeHandler<Signal>,
+  notify: &mpsc::Sender<Notice>,
 ) {
   match message {
     ServerToClient::RegisterUUID(uuid) => {
-      println!("Токен участника: {}", uuid);
+      let mut session = state.session.lock().unwrap();
+      let reconnected = session.uuid.replace(uuid).is_some();
+      let mut loaded = None;
+      if reconnected {
+        // Попытку без ответа сервер забыл вместе со старым соединением.
+        // Её повтор сервер не считает новой попыткой
+        if let Some(entry) = session.history.0.last().filter(|e| e.result.is_none()) {
+          state.send(handler, &Message::Cts(ClientToServer::Guess(entry.guess)));
+        }
+      } else if state.resume == Some(uuid) {
+        let path = state.history_dir.join(format!("{}.bin", uuid));
+        match History::load(&path) {
+          Ok(history) => {
+            loaded = Some(history.0.len());
+            session.history = history;
+          }
+          Err(err) => state.report(Event::Error {
+            message: tr!(Msg::HistoryLoadFailed, err),
+          }),
+        }
+      }
+      state.report(Event::Registered {
+        uuid,
+        history: loaded,
+      });
     }
-    ServerToClient::ExperimentStart(uuid) => {
-      println!("Начало эксперимента!");
-      notify.send(()).unwrap();
-      handler.network().send(
-        state.endpoint,
-        &bincode::serialize(&Message::Cts(ClientToServer::Ack(uuid))).unwrap(),
-      );
+    ServerToClient::RegisterRejected(reason) => {
+      state.report(Event::Rejected { reason });
+      handler.stop();
+      exit(-1);
     }
-    ServerToClient::Answer(guess_result, uuid) => {
-      print!("\nРезультаты попытки: ");
-      match guess_result {
-        GuessResult::Equal => println!("равно"),
-        GuessResult::Less => {
-          println!("меньше");
-          notify.send(()).unwrap();
+    ServerToClient::Kicked { reason, banned } => {
+      state.report(Event::Kicked { reason, banned });
+      handler.stop();
+      exit(-1);
+    }
+    ServerToClient::Shutdown => {
+      state.report(Event::Shutdown);
+      handler.stop();
+      exit(0);
+    }
+    ServerToClient::Leaderboard(leaderboard) => state.report(Event::Leaderboard { leaderboard }),
+    ServerToClient::ReadyCheck => {
+      state.report(Event::ReadyCheck);
+      // Программа, играющая за участника, готова всегда
+      match state.mode {
+        Mode::Interactive => {
+          let _ = notify.send(Notice::ReadyCheck);
         }
-        GuessResult::More => {
-          println!("больше");
-          notify.send(()).unwrap();
+        Mode::Script | Mode::Guesses => state.send(handler, &Message::Cts(ClientToServer::Ready)),
+      }
+    }
+    ServerToClient::Countdown(seconds) => state.report(Event::Countdown { seconds }),
+    ServerToClient::StartCancelled => state.report(Event::StartCancelled),
+    ServerToClient::LateQueued => state.report(Event::LateQueued),
+    ServerToClient::NotStarted => {
+      state.discard_pending(&mut state.session.lock().unwrap());
+      state.report(Event::NotStarted);
+      let _ = notify.send(Notice::Rejected);
+    }
+    ServerToClient::ExperimentStart(uuid, rules) => {
+      let mut session = state.session.lock().unwrap();
+      session.rules = rules;
+      if !std::mem::replace(&mut session.started, true) {
+        state.report(Event::Start {
+          rules: &session.rules,
+        });
+        let _ = notify.send(Notice::Start);
+      }
+      state.send(handler, &Message::Cts(ClientToServer::Ack(uuid)));
+    }
+    ServerToClient::Restart(uuid, rules) => {
+      let mut session = state.session.lock().unwrap();
+      // Прежние попытки остаются у сервера
+      session.history = History::default();
+      session.rules = rules;
+      session.started = true;
+      state.save_history(&session);
+      state.report(Event::Restart {
+        rules: &session.rules,
+      });
+      let _ = notify.send(Notice::Restart);
+      state.send(handler, &Message::Cts(ClientToServer::Ack(uuid)));
+    }
+    ServerToClient::GuessRejected(guess) => {
+      let mut session = state.session.lock().unwrap();
+      state.discard_pending(&mut session);
+      state.report(Event::GuessRejected {
+        guess,
+        domain: &session.rules.domain,
+      });
+      let _ = notify.send(Notice::Rejected);
+    }
+    ServerToClient::OutOfAttempts => {
+      state.discard_pending(&mut state.session.lock().unwrap());
+      state.report(Event::OutOfAttempts);
+      let _ = notify.send(Notice::Out);
+    }
+    ServerToClient::TimeUp => {
+      state.discard_pending(&mut state.session.lock().unwrap());
+      state.report(Event::TimeUp);
+      let _ = notify.send(Notice::Out);
+    }
+    ServerToClient::Answer(guess_result, uuid) => {
+      let mut session = state.session.lock().unwrap();
+      let guess = session
+        .history
+        .answer_last(guess_result)
+        .map(|entry| entry.guess);
+      state.report(Event::Answer {
+        guess,
+        result: guess_result,
+        interval: session.history.interval(session.rules.domain.interval()),
+      });
+      state.save_history(&session);
+      let _ = notify.send(Notice::Answer(guess_result));
+      state.send(handler, &Message::Cts(ClientToServer::Ack(uuid)));
+    }
+  }
+}
+
+fn send_guess(state: &State, handler: &NodeHandler<Signal>, guess: i64) {
+  let mut session = state.session.lock().unwrap();
+  session.history.push_guess(guess);
+  state.save_history(&session);
+  state.send(handler, &Message::Cts(ClientToServer::Guess(guess)));
+}
+
+fn report_history(state: &State) {
+  let session = state.session.lock().unwrap();
+  state.report(Event::History {
+    entries: &session.history.0,
+    interval: session.history.interval(session.rules.domain.interval()),
+  });
+}
+
+/// Ждёт начала эксперимента, подтверждая готовность по запросу админа
+fn wait_start(state: &State, handler: &NodeHandler<Signal>, wait: &mpsc::Receiver<Notice>) {
+  loop {
+    match wait.recv().unwrap() {
+      Notice::Start | Notice::Restart => return,
+      Notice::ReadyCheck => {
+        let mut inp = String::new();
+        if io::stdin().read_line(&mut inp).unwrap() == 0 {
+          handler.stop();
+          exit(0);
         }
+        state.send(handler, &Message::Cts(ClientToServer::Ready));
+        trln!(Msg::WaitingOthers);
       }
-      handler.network().send(
-        state.endpoint,
-        &bincode::serialize(&Message::Cts(ClientToServer::Ack(uuid))).unwrap(),
-      );
-      print!("> ");
-      io::stdout().flush().unwrap();
+      _ => {}
     }
   }
 }
 
-fn handle_input(state: &State, handler: node::NodeHandler<()>, wait: mpsc::Receiver<()>) -> ! {
-  wait.recv().unwrap();
-  println!(
-    "Добро пожаловать!\
-        \n'g' - отправить предположение\
-        \n'h' - посмотреть историю ответов"
-  );
-  let mut history = Vec::new();
+fn handle_input(
+  state: &State,
+  handler: node::NodeHandler<Signal>,
+  wait: mpsc::Receiver<Notice>,
+) -> ! {
+  wait_start(state, &handler, &wait);
+  trln!(Msg::ClientWelcome);
   loop {
     let mut inp = String::new();
-    print!("> ");
+    print!("{}", tr!(Msg::Prompt));
     io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut inp).unwrap();
+    if io::stdin().read_line(&mut inp).unwrap() == 0 {
+      handler.stop();
+      exit(0);
+    }
     match inp.trim() {
       "g" => {
-        print!("Предположение: ");
+        print!("{}", tr!(Msg::GuessPrompt));
         io::stdout().flush().unwrap();
         inp.clear();
         io::stdin().read_line(&mut inp).unwrap();
         let guess = match inp.trim().parse() {
           Err(err) => {
-            println!("Ошибка: {}", err);
+            trln!(Msg::Error, err);
             continue;
           }
           Ok(res) => res,
         };
-        history.push(guess);
-        handler.network().send(
-          state.endpoint,
-          &bincode::serialize(&Message::Cts(ClientToServer::Guess(guess))).unwrap(),
-        );
-      }
-      "h" => {
-        println!("История:");
-        for line in history.chunks(5) {
-          print!("{}", line[0]);
-          for e in line.iter().skip(1) {
-            print!(", {}", e);
-          }
-          println!();
+        send_guess(state, &handler, guess);
+      }
+      "h" => report_history(state),
+      _ => trln!(Msg::InvalidCommand),
+    }
+  }
+}
+
+/// Машинный режим: `guess <число>`, `history` и `quit`, по одной команде на строку.
+/// Когда команды кончаются, дожидается ответа на последнее предположение
+fn run_script(state: &State, handler: node::NodeHandler<Signal>, wait: mpsc::Receiver<Notice>) {
+  wait_start(state, &handler, &wait);
+  let mut quit = false;
+  for line in io::stdin().lock().lines() {
+    let line = line.unwrap();
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next()) {
+      (None, _) => continue,
+      (Some("guess"), Some(guess)) => match guess.parse() {
+        Ok(guess) => send_guess(state, &handler, guess),
+        Err(err) => state.report(Event::Error {
+          message: tr!(Msg::Error, err),
+        }),
+      },
+      (Some("history"), None) => report_history(state),
+      (Some("quit"), None) => {
+        quit = true;
+        break;
+      }
+      _ => state.report(Event::Error {
+        message: tr!(Msg::InvalidCommandLine, line),
+      }),
+    }
+  }
+  let pending = || {
+    let session = state.session.lock().unwrap();
+    session.history.0.last().is_some_and(|e| e.result.is_none())
+  };
+  while !quit && pending() {
+    match wait.recv() {
+      Ok(Notice::Out) | Err(_) => break,
+      Ok(_) => {}
+    }
+  }
+  handler.stop();
+}
+
+/// Машинный режим: отправляет предположения по одному, дожидаясь ответа на каждое
+fn run_guesses(
+  state: &State,
+  handler: node::NodeHandler<Signal>,
+  wait: mpsc::Receiver<Notice>,
+  guesses: Vec<i64>,
+) {
+  wait_start(state, &handler, &wait);
+  let mut solved = false;
+  let mut next = 0;
+  'guesses: while let Some(&guess) = guesses.get(next) {
+    next += 1;
+    send_guess(state, &handler, guess);
+    loop {
+      match wait.recv().unwrap() {
+        Notice::Answer(GuessResult::Equal) => {
+          solved = true;
+          break 'guesses;
+        }
+        Notice::Answer(_) | Notice::Rejected => break,
+        // Заново - значит и предположения с первого
+        Notice::Restart => {
+          next = 0;
+          break;
         }
+        Notice::Out => break 'guesses,
+        Notice::Start | Notice::ReadyCheck => {}
       }
-      _ => println!("Некорректная комманда!"),
     }
   }
+  state.report(Event::Finished { solved });
+  handler.stop();
+}
+
+fn result_name(result: GuessResult) -> &'static str {
+  i18n::text(match result {
+    GuessResult::Equal => Msg::ResultEqual,
+    GuessResult::Less => Msg::ResultLess,
+    GuessResult::More => Msg::ResultMore,
+  })
 }
@@ -1,77 +1,1220 @@
 use std::{
-  collections::HashMap,
-  net::{IpAddr, Ipv4Addr},
+  collections::{HashMap, HashSet, VecDeque},
+  fs,
+  net::{IpAddr, Ipv4Addr, SocketAddr},
+  path::PathBuf,
+  sync::Arc,
+  time::{Duration, Instant},
 };
 
 use hogwarts_guess::{
-  AdminToServer, ClientToServer, Message, ServerToAdmin, ServerToClient, Stats, WaitAnswers,
+  accounts::{self, Accounts, Role},
+  analytics::Analytics,
+  auth::{self, FailureReason, Nonce},
+  bans::{Ban, BanList, BanTarget},
+  config::{self, Config, LateJoiners},
+  export::Export,
+  heartbeat::{self, Signal},
+  history::{History, Reset},
+  i18n::{self, Msg},
+  invites::Invites,
+  leaderboard::{Leaderboard, Ranking, Standing},
+  lockout::Lockout,
+  log_event,
+  logging::{self, Level},
+  metrics::Metrics,
+  profile::Profile,
+  ratelimit::TokenBucket,
+  rules::Rules,
+  strategy,
+  tls::{self, PeerAddrs},
+  trln, AdminToServer, ClientToServer, GuessResult, Message, Outcome, Profiles, RejectReason,
+  ServerToAdmin, ServerToClient, StartAt, WaitAnswers,
 };
 
+use chrono::{DateTime, TimeDelta, Utc};
 use clap::Parser;
 use message_io::{
-  network::{Endpoint, Transport},
-  node::{self, NodeHandler},
+  network::{Endpoint, NetEvent, ResourceId, Transport},
+  node::{self, NodeEvent, NodeHandler},
 };
+use serde::Serialize;
 use uuid::Uuid;
 
 #[derive(Parser)]
-#[command(name = "'Хогвартс Лабораторис' сервер")]
 #[command(version = "0.1")]
-#[command(about = "Сервер эксперимента о угадывании чисел", long_about = None)]
 struct Cli {
-  #[arg(short, long, default_value_t = IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)))]
-  address: IpAddr,
-  #[arg(short, long, default_value_t = 6969)]
-  port: u16,
+  #[arg(short, long)]
+  config: Option<PathBuf>,
+  #[arg(short, long)]
+  address: Option<IpAddr>,
+  #[arg(short, long)]
+  port: Option<u16>,
   #[arg(short = 't', long)]
   auth_token: Option<String>,
+  #[arg(long, conflicts_with = "auth_token")]
+  accounts: Option<PathBuf>,
+  #[arg(long)]
+  invites: Option<PathBuf>,
+  #[arg(long)]
+  require_invite: bool,
+  #[arg(long)]
+  auth_max_failures: Option<u32>,
+  #[arg(long)]
+  auth_lockout_secs: Option<u64>,
+  #[arg(long)]
+  auth_lockout_max_secs: Option<u64>,
+  #[arg(long)]
+  max_payload_bytes: Option<usize>,
+  #[arg(long)]
+  max_messages_per_sec: Option<u32>,
+  #[arg(long)]
+  max_guesses_per_sec: Option<u32>,
+  #[arg(long)]
+  heartbeat_secs: Option<u64>,
+  #[arg(long)]
+  idle_timeout_secs: Option<u64>,
+  #[arg(long, value_enum)]
+  log_level: Option<Level>,
+  #[arg(long, value_enum)]
+  log_format: Option<logging::Format>,
+  #[arg(long)]
+  log_file: Option<PathBuf>,
+  #[arg(long)]
+  metrics_address: Option<SocketAddr>,
+  #[arg(long)]
+  tls_cert: Option<PathBuf>,
+  #[arg(long)]
+  tls_key: Option<PathBuf>,
+  #[arg(long)]
+  report_file: Option<PathBuf>,
+  #[arg(long)]
+  export_dir: Option<PathBuf>,
+  #[arg(long, value_enum)]
+  ranking: Option<Ranking>,
+  #[arg(long)]
+  seconds_per_guess: Option<u64>,
+  #[arg(long)]
+  publish_leaderboard: bool,
+  #[arg(long, allow_negative_numbers = true, conflicts_with = "values")]
+  low: Option<i64>,
+  #[arg(long, allow_negative_numbers = true, conflicts_with = "values")]
+  high: Option<i64>,
+  #[arg(long, conflicts_with = "values")]
+  step: Option<u64>,
+  #[arg(long, value_delimiter = ',', num_args = 1.., allow_negative_numbers = true)]
+  values: Option<Vec<i64>>,
+  #[arg(long)]
+  max_attempts: Option<u64>,
+  #[arg(long)]
+  time_limit_secs: Option<u64>,
+  #[arg(long)]
+  guess_time_limit_secs: Option<u64>,
+  #[arg(long, value_enum)]
+  late_joiners: Option<LateJoiners>,
+}
+
+impl Cli {
+  /// Поверх файла настроек записывает то, что задано флагами
+  fn apply(self, config: &mut Config) {
+    fn set<T>(field: &mut T, value: Option<T>) {
+      if let Some(value) = value {
+        *field = value;
+      }
+    }
+    set(&mut config.listen.address, self.address);
+    set(&mut config.listen.port, self.port);
+    set(
+      &mut config.listen.metrics_address,
+      self.metrics_address.map(Some),
+    );
+    set(&mut config.tls.cert, self.tls_cert.map(Some));
+    set(&mut config.tls.key, self.tls_key.map(Some));
+    // Токен и файл учётных записей заменяют друг друга
+    if self.auth_token.is_some() {
+      config.auth.accounts = None;
+      config.auth.token = self.auth_token;
+    }
+    if self.accounts.is_some() {
+      config.auth.token = None;
+      config.auth.accounts = self.accounts;
+    }
+    set(&mut config.auth.max_failures, self.auth_max_failures);
+    set(&mut config.auth.lockout_secs, self.auth_lockout_secs);
+    set(
+      &mut config.auth.lockout_max_secs,
+      self.auth_lockout_max_secs,
+    );
+    set(&mut config.enrollment.invites, self.invites.map(Some));
+    config.enrollment.require_invite |= self.require_invite;
+    set(&mut config.limits.max_payload_bytes, self.max_payload_bytes);
+    set(
+      &mut config.limits.max_messages_per_sec,
+      self.max_messages_per_sec,
+    );
+    set(
+      &mut config.limits.max_guesses_per_sec,
+      self.max_guesses_per_sec,
+    );
+    set(&mut config.limits.heartbeat_secs, self.heartbeat_secs);
+    set(&mut config.limits.idle_timeout_secs, self.idle_timeout_secs);
+    set(&mut config.log.level, self.log_level);
+    set(&mut config.log.format, self.log_format);
+    set(&mut config.log.file, self.log_file.map(Some));
+    set(
+      &mut config.persistence.report_file,
+      self.report_file.map(Some),
+    );
+    set(
+      &mut config.persistence.export_dir,
+      self.export_dir.map(Some),
+    );
+    set(&mut config.leaderboard.ranking, self.ranking);
+    set(
+      &mut config.leaderboard.seconds_per_guess,
+      self.seconds_per_guess,
+    );
+    config.leaderboard.publish_on_shutdown |= self.publish_leaderboard;
+    // Границы и список чисел тоже заменяют друг друга
+    if self.low.is_some() || self.high.is_some() || self.step.is_some() {
+      config.experiment.values = None;
+    }
+    set(&mut config.experiment.low, self.low.map(Some));
+    set(&mut config.experiment.high, self.high.map(Some));
+    set(&mut config.experiment.step, self.step.map(Some));
+    if self.values.is_some() {
+      config.experiment.low = None;
+      config.experiment.high = None;
+      config.experiment.step = None;
+      config.experiment.values = self.values;
+    }
+    set(
+      &mut config.experiment.max_attempts,
+      self.max_attempts.map(Some),
+    );
+    set(
+      &mut config.experiment.time_limit_secs,
+      self.time_limit_secs.map(Some),
+    );
+    set(
+      &mut config.experiment.guess_time_limit_secs,
+      self.guess_time_limit_secs.map(Some),
+    );
+    set(&mut config.experiment.late_joiners, self.late_joiners);
+  }
 }
 
 #[derive(PartialEq, Eq)]
 enum EndpointStatus {
   JustConnected,
+  /// Админу выдан вызов, ждём ответ на него
+  Challenged {
+    name: String,
+    nonce: Nonce,
+  },
   AuthedAsUser(Uuid),
-  AuthedAsAdmin,
+  AuthedAsAdmin {
+    name: String,
+    role: Role,
+  },
+}
+
+/// Допуск участников к эксперименту
+struct Enrollment {
+  invites: Invites,
+  /// Регистрация только по коду приглашения
+  required: bool,
+  /// Все выданные UUID
+  participants: HashMap<Uuid, Participant>,
+}
+
+struct Participant {
+  /// Метка кода приглашения, по которому зарегистрирован участник
+  invite: Option<String>,
+  profile: Profile,
+}
+
+impl Enrollment {
+  /// Записывает участника и возвращает его отображаемое имя. Не заданное имя заменяется
+  /// меткой приглашения или началом UUID, занятое дополняется номером
+  fn enroll(&mut self, uuid: Uuid, invite: Option<String>, mut profile: Profile) -> String {
+    let mut name = match (profile.name.take(), &invite) {
+      (Some(name), _) => name,
+      (None, Some(label)) => label.clone(),
+      (None, None) => uuid.simple().to_string()[..8].to_string(),
+    };
+    let taken = |name: &str| {
+      self
+        .participants
+        .values()
+        .any(|p| p.profile.name().to_lowercase() == name.to_lowercase())
+    };
+    if taken(&name) {
+      name = (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|name| !taken(name))
+        .unwrap();
+    }
+    profile.name = Some(name.clone());
+    self
+      .participants
+      .insert(uuid, Participant { invite, profile });
+    name
+  }
+
+  fn profiles<'a>(&self, uuids: impl Iterator<Item = &'a Uuid>) -> Profiles {
+    Profiles(
+      uuids
+        .filter_map(|uuid| Some((*uuid, self.participants.get(uuid)?.profile.clone())))
+        .collect(),
+    )
+  }
+}
+
+/// Защита от флуда: размер и частота сообщений с одного соединения
+struct Flood {
+  max_payload: usize,
+  messages_per_sec: u32,
+  guesses_per_sec: u32,
+  buckets: HashMap<Endpoint, Buckets>,
+}
+
+struct Buckets {
+  messages: TokenBucket,
+  guesses: TokenBucket,
+}
+
+impl Flood {
+  fn track(&mut self, endpoint: Endpoint) {
+    let now = Instant::now();
+    let buckets = Buckets {
+      messages: TokenBucket::new(self.messages_per_sec, now),
+      guesses: TokenBucket::new(self.guesses_per_sec, now),
+    };
+    self.buckets.insert(endpoint, buckets);
+  }
+
+  fn take_message(&mut self, endpoint: Endpoint) -> bool {
+    let now = Instant::now();
+    self
+      .buckets
+      .get_mut(&endpoint)
+      .is_none_or(|buckets| buckets.messages.take(now))
+  }
+
+  fn take_guess(&mut self, endpoint: Endpoint) -> bool {
+    let now = Instant::now();
+    self
+      .buckets
+      .get_mut(&endpoint)
+      .is_none_or(|buckets| buckets.guesses.take(now))
+  }
+}
+
+/// Пинги и закрытие молчащих соединений
+struct Heartbeat {
+  /// Как часто рассылать пинги, ноль - не рассылать
+  interval: Duration,
+  /// Сколько ждать сообщений от соединения, ноль - сколько угодно
+  idle_timeout: Duration,
+  last_seen: HashMap<Endpoint, Instant>,
+}
+
+/// Отложенный старт эксперимента
+struct Schedule {
+  at: StartAt,
+  /// Кто назначил старт
+  admin: String,
+  /// Подтвердившие готовность, `None` - старт без проверки готовности
+  ready: Option<HashSet<Uuid>>,
+  /// Момент старта, известен с начала отсчёта
+  start_at: Option<DateTime<Utc>>,
+}
+
+/// Сколько последних неудачных попыток аутентификации хранить
+const AUTH_FAILURES_KEPT: usize = 1000;
+
+/// Сколько ждать после оповещения о завершении, прежде чем остановиться
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
+/// Итоговый отчёт, который сервер пишет при завершении работы
+#[derive(Serialize)]
+struct Report {
+  finished_at: DateTime<Utc>,
+  participants: Vec<ReportEntry>,
+}
+
+#[derive(Serialize)]
+struct ReportEntry {
+  uuid: Uuid,
+  profile: Profile,
+  guesses: u64,
+  /// Попытка, которая так и осталась без ответа
+  awaiting_answer: Option<i64>,
 }
 
 struct ServerState {
-  auth_token: String,
+  accounts: Accounts,
+  /// После стольких неудач подряд соединение закрывается
+  auth_max_failures: u32,
+  /// Неудачи на текущем соединении
+  auth_attempts: HashMap<Endpoint, u32>,
+  auth_lockout: Lockout<IpAddr>,
+  auth_failures: VecDeque<auth::Failure>,
+  enrollment: Enrollment,
+  bans: BanList,
+  flood: Flood,
+  heartbeat: Heartbeat,
   clients: HashMap<Endpoint, EndpointStatus>,
-  handler: NodeHandler<()>,
+  handler: NodeHandler<Signal>,
+  /// Слушатель, который закрывается при завершении работы
+  listener: ResourceId,
+  /// Внешний слушатель, если сервер за TLS-туннелем
+  tls_listener: Option<tls::Listener>,
+  shutting_down: bool,
+  /// Куда записать итоговый отчёт и выгрузить результаты при завершении работы
+  persistence: config::Persistence,
+  leaderboard: config::Leaderboard,
+  /// Допустимые числа и ограничения участников
+  rules: Rules,
+  late_joiners: LateJoiners,
+  /// Подключившиеся после старта, ждущие допуска, в порядке подключения
+  late_queue: Vec<Uuid>,
+  /// Выбывшие, не угадав
+  outcomes: HashMap<Uuid, Outcome>,
+  /// Когда админ начал эксперимент
+  started_at: Option<DateTime<Utc>>,
+  /// Когда эксперимент начался для каждого участника: у опоздавших позже
+  started: HashMap<Uuid, DateTime<Utc>>,
+  schedule: Option<Schedule>,
+  /// Идёт цепочка таймеров `Signal::Countdown`
+  countdown_running: bool,
   uuids_to_endpoints: HashMap<Uuid, Endpoint>,
-  stat_users: Stats,
   waiting_users: WaitAnswers,
+  /// Все попытки участников с ответами, переживают отключения
+  histories: HashMap<Uuid, History>,
+  /// Сброшенный админом прогресс участников, по порядку
+  resets: HashMap<Uuid, Vec<Reset>>,
+  /// Сброшенные участники, ещё не подтвердившие `Restart`, и уид этого сообщения.
+  /// Отключённым он отправляется при возвращении
+  pending_restarts: HashMap<Uuid, Uuid>,
+  /// Когда была получена ожидающая ответа попытка
+  guessed_at: HashMap<Uuid, Instant>,
+  metrics: Arc<Metrics>,
+  /// Настоящие адреса клиентов, подключённых через TLS
+  peers: PeerAddrs,
 }
 
 impl ServerState {
-  fn new(auth_token: String, handler: NodeHandler<()>) -> Self {
+  #[allow(clippy::too_many_arguments)]
+  fn new(
+    accounts: Accounts,
+    auth_max_failures: u32,
+    auth_lockout: Lockout<IpAddr>,
+    enrollment: Enrollment,
+    flood: Flood,
+    heartbeat: Heartbeat,
+    handler: NodeHandler<Signal>,
+    listener: ResourceId,
+    tls_listener: Option<tls::Listener>,
+    persistence: config::Persistence,
+    leaderboard: config::Leaderboard,
+    rules: Rules,
+    late_joiners: LateJoiners,
+    metrics: Arc<Metrics>,
+    peers: PeerAddrs,
+  ) -> Self {
     Self {
-      auth_token,
+      accounts,
+      auth_max_failures,
+      auth_attempts: HashMap::new(),
+      auth_lockout,
+      auth_failures: VecDeque::new(),
+      enrollment,
+      bans: BanList::default(),
+      flood,
+      heartbeat,
       clients: HashMap::new(),
       handler,
-      stat_users: Stats(HashMap::new()),
+      listener,
+      tls_listener,
+      shutting_down: false,
+      persistence,
+      leaderboard,
+      rules,
+      late_joiners,
+      late_queue: Vec::new(),
+      outcomes: HashMap::new(),
+      started_at: None,
+      started: HashMap::new(),
+      schedule: None,
+      countdown_running: false,
       waiting_users: WaitAnswers(HashMap::new()),
+      histories: HashMap::new(),
+      resets: HashMap::new(),
+      pending_restarts: HashMap::new(),
+      guessed_at: HashMap::new(),
       uuids_to_endpoints: HashMap::new(),
+      metrics,
+      peers,
+    }
+  }
+
+  fn update_gauges(&self) {
+    let count =
+      |pred: fn(&EndpointStatus) -> bool| self.clients.values().filter(|s| pred(s)).count();
+    self
+      .metrics
+      .participants
+      .set(count(|s| matches!(s, EndpointStatus::AuthedAsUser(_))));
+    self
+      .metrics
+      .admins
+      .set(count(|s| matches!(s, EndpointStatus::AuthedAsAdmin { .. })));
+    self.metrics.pending_answers.set(self.waiting_users.0.len());
+  }
+
+  /// Перестаёт принимать подключения, пишет итоги, оповещает участников и админов
+  /// и через паузу останавливает сервер
+  fn shutdown(&mut self, initiator: &str) {
+    if self.shutting_down {
+      return;
+    }
+    self.shutting_down = true;
+    log_event!(Level::Info, Msg::ShuttingDown, initiator = initiator);
+    self.handler.network().remove(self.listener);
+    if let Some(listener) = &self.tls_listener {
+      listener.stop();
+    }
+    // Итоги считаются до оповещения: отключившиеся участники пропадают из статистики
+    self.final_report();
+    if self.leaderboard.publish_on_shutdown {
+      self.publish_leaderboard("shutdown");
+    }
+    if let Some(dir) = &self.persistence.export_dir {
+      match self.export().write(dir) {
+        Ok(_) => log_event!(Level::Info, Msg::ResultsExported, dir = dir.display()),
+        Err(err) => log_event!(Level::Error, Msg::ResultsExportFailed, error = err),
+      }
+    }
+    for (endpoint, status) in &self.clients {
+      let message = match status {
+        EndpointStatus::AuthedAsUser(_) => Message::Stc(ServerToClient::Shutdown),
+        EndpointStatus::AuthedAsAdmin { .. } => Message::Sta(ServerToAdmin::Shutdown),
+        _ => continue,
+      };
+      self
+        .handler
+        .network()
+        .send(*endpoint, &bincode::serialize(&message).unwrap());
+    }
+    self
+      .handler
+      .signals()
+      .send_with_timer(Signal::Stop, SHUTDOWN_GRACE);
+  }
+
+  /// Рассылает начало эксперимента подключённым участникам
+  fn start(&mut self, initiator: &str) {
+    log_event!(Level::Info, Msg::StartBroadcastBegin, admin = initiator);
+    // Прямой старт заменяет назначенный
+    self.schedule = None;
+    self.started_at.get_or_insert_with(Utc::now);
+    // Прямой старт допускает и ждущих опоздавших
+    self.late_queue.clear();
+    // Уже начавших повторный старт не трогает
+    let participants: Vec<_> = self
+      .clients
+      .iter()
+      .filter_map(|(endpoint, status)| match status {
+        EndpointStatus::AuthedAsUser(uuid) if !self.started.contains_key(uuid) => {
+          Some((*endpoint, *uuid))
+        }
+        _ => None,
+      })
+      .collect();
+    for (endpoint, uuid) in participants {
+      self.start_participant(endpoint, uuid);
+    }
+    log_event!(Level::Info, Msg::StartBroadcastEnd)
+  }
+
+  /// Отправляет участнику старт, время участника идёт с первого
+  fn start_participant(&mut self, endpoint: Endpoint, uuid: Uuid) {
+    self.started.entry(uuid).or_insert_with(Utc::now);
+    let msg_uuid = Uuid::new_v4();
+    log_event!(
+      Level::Debug,
+      Msg::Sending,
+      endpoint = endpoint,
+      message = msg_uuid
+    );
+    self.handler.network().send(
+      endpoint,
+      &bincode::serialize(&Message::Stc(ServerToClient::ExperimentStart(
+        msg_uuid,
+        self.rules.clone(),
+      )))
+      .unwrap(),
+    );
+  }
+
+  /// Участник подключился после старта: начинает сразу или встаёт в очередь
+  fn late_join(&mut self, endpoint: Endpoint, uuid: Uuid) {
+    let queued = self.late_joiners == LateJoiners::Queue;
+    log_event!(Level::Info, Msg::LateJoiner, target = uuid, queued = queued);
+    if queued {
+      self.late_queue.push(uuid);
+      self.handler.network().send(
+        endpoint,
+        &bincode::serialize(&Message::Stc(ServerToClient::LateQueued)).unwrap(),
+      );
+    } else {
+      self.start_participant(endpoint, uuid);
+    }
+    let name = self
+      .enrollment
+      .participants
+      .get(&uuid)
+      .map(|participant| participant.profile.name().to_string())
+      .unwrap_or_default();
+    self.notify_admins(ServerToAdmin::LateJoin { uuid, name, queued });
+  }
+
+  /// Сбрасывает прогресс участников, сохраняя прежние попытки, и начинает для них
+  /// эксперимент заново. Возвращает, скольким сброшен прогресс
+  fn restart(&mut self, admin: &str, targets: Vec<Uuid>) -> usize {
+    if self.started_at.is_none() {
+      return 0;
+    }
+    let now = Utc::now();
+    let targets: HashSet<Uuid> = targets.into_iter().collect();
+    let mut restarted = 0;
+    for uuid in targets {
+      if !self.enrollment.participants.contains_key(&uuid) {
+        continue;
+      }
+      restarted += 1;
+      log_event!(
+        Level::Info,
+        Msg::ParticipantRestarted,
+        admin = admin,
+        target = uuid
+      );
+      let history = self.histories.remove(&uuid).unwrap_or_default();
+      self.resets.entry(uuid).or_default().push(Reset {
+        at: now,
+        admin: admin.to_string(),
+        history,
+      });
+      self.outcomes.remove(&uuid);
+      self.waiting_users.0.remove(&uuid);
+      self.guessed_at.remove(&uuid);
+      self.late_queue.retain(|queued| *queued != uuid);
+      self.started.insert(uuid, now);
+      let msg_uuid = Uuid::new_v4();
+      self.pending_restarts.insert(uuid, msg_uuid);
+      if let Some(&endpoint) = self.uuids_to_endpoints.get(&uuid) {
+        self.send_restart(endpoint, msg_uuid);
+      }
+    }
+    restarted
+  }
+
+  fn send_restart(&self, endpoint: Endpoint, msg_uuid: Uuid) {
+    let restart = ServerToClient::Restart(msg_uuid, self.rules.clone());
+    self.handler.network().send(
+      endpoint,
+      &bincode::serialize(&Message::Stc(restart)).unwrap(),
+    );
+  }
+
+  /// Начинает эксперимент для ждущих в очереди, возвращает их число
+  fn admit_late(&mut self, admin: &str) -> usize {
+    let queue = std::mem::take(&mut self.late_queue);
+    log_event!(
+      Level::Info,
+      Msg::AdmittingLate,
+      admin = admin,
+      participants = queue.len()
+    );
+    for uuid in &queue {
+      if let Some(endpoint) = self.uuids_to_endpoints.get(uuid) {
+        self.start_participant(*endpoint, *uuid);
+      }
+    }
+    queue.len()
+  }
+
+  /// Назначает старт: с проверкой готовности отсчёт начнётся, когда все будут готовы
+  fn schedule_start(&mut self, admin: String, at: StartAt, ready_check: bool) {
+    log_event!(
+      Level::Info,
+      Msg::StartScheduling,
+      admin = admin,
+      at = format!("{:?}", at),
+      ready_check = ready_check
+    );
+    self.schedule = Some(Schedule {
+      at,
+      admin,
+      ready: ready_check.then(HashSet::new),
+      start_at: None,
+    });
+    if ready_check {
+      self.notify_participants(ServerToClient::ReadyCheck);
+      self.check_lobby();
+    } else {
+      self.begin_countdown();
+    }
+  }
+
+  fn cancel_start(&mut self, admin: &str) {
+    if self.schedule.take().is_none() {
+      return;
+    }
+    log_event!(Level::Info, Msg::ScheduleCancelled, admin = admin);
+    self.notify_participants(ServerToClient::StartCancelled);
+    self.notify_admins(ServerToAdmin::StartCancelled);
+  }
+
+  /// Сообщает админам, сколько участников готово, и начинает отсчёт, когда готовы все
+  fn check_lobby(&mut self) {
+    let Some(Schedule {
+      ready: Some(ready),
+      start_at: None,
+      ..
+    }) = &self.schedule
+    else {
+      return;
+    };
+    let total = self.uuids_to_endpoints.len();
+    let ready = self
+      .uuids_to_endpoints
+      .keys()
+      .filter(|uuid| ready.contains(uuid))
+      .count();
+    self.notify_admins(ServerToAdmin::Lobby { ready, total });
+    if total > 0 && ready == total {
+      self.begin_countdown();
+    }
+  }
+
+  fn begin_countdown(&mut self) {
+    let Some(schedule) = &mut self.schedule else {
+      return;
+    };
+    let Some(start_at) = start_time(Utc::now(), schedule.at) else {
+      // Проверено при назначении, но ожидание готовности могло затянуться
+      let admin = schedule.admin.clone();
+      return self.cancel_start(&admin);
+    };
+    schedule.start_at = Some(start_at);
+    log_event!(Level::Info, Msg::CountdownStarted, at = start_at);
+    self.notify_admins(ServerToAdmin::StartScheduled(start_at));
+    if !self.countdown_running {
+      self.countdown_running = true;
+      self.handler.signals().send(Signal::Countdown);
+    }
+  }
+
+  /// Рассылает секунды до старта и начинает эксперимент, когда они кончаются
+  fn countdown(&mut self) {
+    let Some(start_at) = self.schedule.as_ref().and_then(|s| s.start_at) else {
+      // Старт отменили или начали вручную
+      self.countdown_running = false;
+      return;
+    };
+    let remaining = (start_at - Utc::now()).num_milliseconds();
+    if remaining <= 0 {
+      self.countdown_running = false;
+      let admin = self.schedule.take().map(|s| s.admin).unwrap_or_default();
+      return self.start(&admin);
+    }
+    let secs = (remaining + 999) / 1000;
+    self.notify_participants(ServerToClient::Countdown(secs as u64));
+    // Следующий тик - ровно на границе секунды до старта
+    let next = Duration::from_millis((remaining - (secs - 1) * 1000) as u64);
+    self
+      .handler
+      .signals()
+      .send_with_timer(Signal::Countdown, next);
+  }
+
+  fn notify_participants(&self, message: ServerToClient) {
+    let data = bincode::serialize(&Message::Stc(message)).unwrap();
+    for endpoint in self.uuids_to_endpoints.values() {
+      self.handler.network().send(*endpoint, &data);
+    }
+  }
+
+  fn notify_admins(&self, message: ServerToAdmin) {
+    let data = bincode::serialize(&Message::Sta(message)).unwrap();
+    for (endpoint, status) in &self.clients {
+      if let EndpointStatus::AuthedAsAdmin { .. } = status {
+        self.handler.network().send(*endpoint, &data);
+      }
+    }
+  }
+
+  /// Лидерборд по всем участникам, в том числе отключившимся
+  fn leaderboard(&self) -> Leaderboard {
+    let standings = self
+      .enrollment
+      .participants
+      .iter()
+      .map(|(uuid, participant)| {
+        let entries = self.histories.get(uuid).map_or(&[][..], |h| &h.0[..]);
+        let solved = entries
+          .iter()
+          .position(|entry| entry.result == Some(GuessResult::Equal));
+        let time_to_solve = solved.and_then(|i| {
+          let solved_at = entries[i].answered_at?;
+          (solved_at - *self.started.get(uuid)?).to_std().ok()
+        });
+        let standing = Standing {
+          uuid: Some(*uuid),
+          name: participant.profile.name().to_string(),
+          guesses: solved.map_or(entries.len(), |i| i + 1) as u64,
+          time_to_solve,
+          rank: None,
+          outcome: self.outcomes.get(uuid).copied(),
+          assessment: self
+            .histories
+            .get(uuid)
+            .and_then(|history| strategy::classify(self.rules.domain.interval(), history)),
+        };
+        (standing, solved.is_some())
+      });
+    Leaderboard::new(
+      self.leaderboard.ranking,
+      self.leaderboard.seconds_per_guess,
+      standings,
+    )
+  }
+
+  /// Рассылает лидерборд подключённым участникам, возвращает их число
+  fn publish_leaderboard(&self, initiator: &str) -> usize {
+    let leaderboard = self.leaderboard();
+    let participants: Vec<_> = self
+      .clients
+      .iter()
+      .filter_map(|(endpoint, status)| match status {
+        EndpointStatus::AuthedAsUser(uuid) => Some((*endpoint, *uuid)),
+        _ => None,
+      })
+      .collect();
+    log_event!(
+      Level::Info,
+      Msg::PublishingLeaderboard,
+      initiator = initiator,
+      participants = participants.len()
+    );
+    for (endpoint, uuid) in &participants {
+      let message = ServerToClient::Leaderboard(leaderboard.for_participant(*uuid));
+      self.handler.network().send(
+        *endpoint,
+        &bincode::serialize(&Message::Stc(message)).unwrap(),
+      );
+    }
+    participants.len()
+  }
+
+  fn export(&self) -> Export {
+    let empty = History::default();
+    let export = Export::new(
+      self
+        .enrollment
+        .participants
+        .iter()
+        .map(|(uuid, participant)| {
+          let history = self.histories.get(uuid).unwrap_or(&empty);
+          let outcome = self.outcomes.get(uuid).copied();
+          let resets = self.resets.get(uuid).map_or(&[][..], |r| &r[..]);
+          (*uuid, &participant.profile, history, outcome, resets)
+        }),
+    );
+    Export {
+      analytics: self.analytics(),
+      ..export
+    }
+  }
+
+  /// Сводка по всем участникам, в том числе ещё не угадывавшим
+  fn analytics(&self) -> Analytics {
+    let empty = History::default();
+    Analytics::new(
+      &self.rules.domain,
+      self
+        .enrollment
+        .participants
+        .keys()
+        .map(|uuid| self.histories.get(uuid).unwrap_or(&empty)),
+    )
+  }
+
+  /// Пишет итоги в журнал, а полный отчёт - в файл, если он задан
+  /// Попытки берутся из историй: они, в отличие от соединений, переживают отключения
+  fn final_report(&self) {
+    let mut participants: Vec<ReportEntry> = self
+      .enrollment
+      .participants
+      .iter()
+      .map(|(uuid, participant)| {
+        let entries = self.histories.get(uuid).map_or(&[][..], |h| &h.0[..]);
+        ReportEntry {
+          uuid: *uuid,
+          profile: participant.profile.clone(),
+          guesses: entries.len() as u64,
+          awaiting_answer: entries
+            .last()
+            .filter(|entry| entry.result.is_none())
+            .map(|entry| entry.guess),
+        }
+      })
+      .collect();
+    participants.sort_by(|a, b| a.profile.name().cmp(b.profile.name()));
+    log_event!(
+      Level::Info,
+      Msg::FinalStats,
+      participants = participants.len(),
+      guesses = participants.iter().map(|p| p.guesses).sum::<u64>(),
+      awaiting = participants
+        .iter()
+        .filter(|p| p.awaiting_answer.is_some())
+        .count()
+    );
+    let Some(path) = &self.persistence.report_file else {
+      return;
+    };
+    let report = Report {
+      finished_at: Utc::now(),
+      participants,
+    };
+    let written = serde_json::to_string_pretty(&report)
+      .map_err(|err| err.to_string())
+      .and_then(|data| fs::write(path, data).map_err(|err| err.to_string()));
+    match written {
+      Ok(()) => log_event!(Level::Info, Msg::ReportWritten, file = path.display()),
+      Err(err) => log_event!(Level::Error, Msg::ReportFailed, error = err),
     }
   }
 
   fn register(&mut self, endpoint: Endpoint) {
     self.clients.insert(endpoint, EndpointStatus::JustConnected);
+    self.flood.track(endpoint);
+    self.heartbeat.last_seen.insert(endpoint, Instant::now());
   }
 
   fn unregister(&mut self, endpoint: Endpoint) {
     if let Some(EndpointStatus::AuthedAsUser(uuid)) = self.clients.get(&endpoint) {
-      self.stat_users.0.remove(uuid);
       self.waiting_users.0.remove(uuid);
+      self.guessed_at.remove(uuid);
       self.uuids_to_endpoints.remove(uuid);
+      // При повторном подключении снова встанет в очередь
+      self.late_queue.retain(|queued| queued != uuid);
+    };
+    self.auth_attempts.remove(&endpoint);
+    self.flood.buckets.remove(&endpoint);
+    self.heartbeat.last_seen.remove(&endpoint);
+    let status = self.clients.remove(&endpoint);
+    // Ушёл, возможно, последний неготовый участник
+    if let Some(EndpointStatus::AuthedAsUser(_)) = status {
+      self.check_lobby();
+    }
+  }
+
+  /// Закрывает соединение со стороны сервера
+  fn disconnect(&mut self, endpoint: Endpoint) {
+    log_event!(Level::Info, Msg::EndpointDropped, endpoint = endpoint);
+    self.handler.network().remove(endpoint.resource_id());
+    self.unregister(endpoint);
+  }
+
+  /// Отключает соединение, превысившее ограничение `limit`
+  fn flooded(&mut self, endpoint: Endpoint, limit: &str) {
+    log_event!(
+      Level::Warn,
+      Msg::FloodDetected,
+      endpoint = endpoint,
+      limit = limit
+    );
+    self.metrics.flood_disconnects.inc();
+    self.disconnect(endpoint);
+  }
+
+  /// Вышло ли у участника время на эксперимент или на очередную попытку.
+  /// Пока участник ждёт ответа или если он уже угадал, время не выходит
+  fn time_up(&self, uuid: &Uuid) -> bool {
+    let Some(started_at) = self.started.get(uuid).copied() else {
+      return false;
+    };
+    let entries = self.histories.get(uuid).map_or(&[][..], |h| &h.0[..]);
+    let last = entries.last();
+    let solved = entries
+      .iter()
+      .any(|entry| entry.result == Some(GuessResult::Equal));
+    if solved || last.is_some_and(|entry| entry.result.is_none()) {
+      return false;
+    }
+    let now = Utc::now();
+    let exceeded = |since: DateTime<Utc>, limit: Option<u64>| {
+      limit.is_some_and(|secs| {
+        (now - since)
+          .to_std()
+          .is_ok_and(|elapsed| elapsed > Duration::from_secs(secs))
+      })
+    };
+    let answered_at = last.and_then(|entry| entry.answered_at);
+    exceeded(started_at, self.rules.time_limit_secs)
+      || exceeded(
+        answered_at.unwrap_or(started_at),
+        self.rules.guess_time_limit_secs,
+      )
+  }
+
+  /// Отмечает участников, у которых вышло время, в том числе отключившихся,
+  /// и через секунду проверяет снова
+  fn check_deadlines(&mut self) {
+    let expired: Vec<Uuid> = self
+      .started
+      .keys()
+      .filter(|uuid| !self.outcomes.contains_key(uuid) && self.time_up(uuid))
+      .copied()
+      .collect();
+    for uuid in expired {
+      self.finish(uuid, Outcome::TimeUp);
+    }
+    self
+      .handler
+      .signals()
+      .send_with_timer(Signal::Deadlines, heartbeat::CHECK_INTERVAL);
+  }
+
+  /// Отмечает, что участник выбыл, и сообщает ему об этом, если он подключён
+  fn finish(&mut self, uuid: Uuid, outcome: Outcome) {
+    if self.outcomes.insert(uuid, outcome).is_none() {
+      log_event!(
+        Level::Info,
+        Msg::ParticipantFinished,
+        target = uuid,
+        outcome = format!("{:?}", outcome)
+      );
+    }
+    let Some(endpoint) = self.uuids_to_endpoints.get(&uuid) else {
+      return;
+    };
+    let message = match outcome {
+      Outcome::OutOfAttempts => ServerToClient::OutOfAttempts,
+      Outcome::TimeUp => ServerToClient::TimeUp,
+    };
+    self.handler.network().send(
+      *endpoint,
+      &bincode::serialize(&Message::Stc(message)).unwrap(),
+    );
+  }
+
+  /// Закрывает соединения, молчащие дольше таймаута, остальным шлёт пинг
+  fn heartbeat(&mut self) {
+    let now = Instant::now();
+    let timeout = self.heartbeat.idle_timeout;
+    let idle: Vec<Endpoint> = self
+      .heartbeat
+      .last_seen
+      .iter()
+      .filter(|(_, seen)| !timeout.is_zero() && now.duration_since(**seen) > timeout)
+      .map(|(endpoint, _)| *endpoint)
+      .collect();
+    for endpoint in idle {
+      log_event!(Level::Info, Msg::EndpointIdle, endpoint = endpoint);
+      self.metrics.idle_disconnects.inc();
+      self.disconnect(endpoint);
+    }
+    let ping = bincode::serialize(&Message::Ping).unwrap();
+    for endpoint in self.clients.keys() {
+      self.handler.network().send(*endpoint, &ping);
+    }
+    self
+      .handler
+      .signals()
+      .send_with_timer(Signal::Heartbeat, self.heartbeat.interval);
+  }
+
+  /// Адрес собеседника на другой стороне соединения
+  fn peer_addr(&self, endpoint: Endpoint) -> SocketAddr {
+    let addr = endpoint.addr();
+    self
+      .peers
+      .lock()
+      .unwrap()
+      .get(&addr)
+      .copied()
+      .unwrap_or(addr)
+  }
+
+  fn send_admin(&self, endpoint: Endpoint, message: ServerToAdmin) {
+    self.handler.network().send(
+      endpoint,
+      &bincode::serialize(&Message::Sta(message)).unwrap(),
+    );
+  }
+
+  /// Учитывает неудачную аутентификацию: пишет в журнал, блокирует адрес
+  /// и закрывает соединение после `auth_max_failures` неудач подряд
+  fn auth_failed(&mut self, endpoint: Endpoint, name: Option<String>, reason: FailureReason) {
+    let now = Instant::now();
+    let address = self.peer_addr(endpoint);
+    log_event!(
+      Level::Warn,
+      Msg::AdminAuthFailed,
+      endpoint = endpoint,
+      admin = name.as_deref().unwrap_or("-"),
+      reason = format!("{:?}", reason)
+    );
+    self.metrics.auth_failures.inc();
+    if self.auth_failures.len() == AUTH_FAILURES_KEPT {
+      self.auth_failures.pop_front();
+    }
+    self.auth_failures.push_back(auth::Failure {
+      at: chrono::Utc::now(),
+      address,
+      name,
+      reason,
+    });
+
+    if reason == FailureReason::Locked {
+      let secs = self
+        .auth_lockout
+        .locked_for(&address.ip(), now)
+        .unwrap_or_default()
+        .as_secs_f64()
+        .ceil() as u64;
+      self.send_admin(endpoint, ServerToAdmin::AuthLocked(secs));
+      return self.disconnect(endpoint);
+    }
+
+    self.auth_lockout.prune(now);
+    let lockout = self.auth_lockout.fail(address.ip(), now);
+    log_event!(
+      Level::Warn,
+      Msg::AuthLockout,
+      address = address.ip(),
+      secs = lockout.as_secs_f64()
+    );
+    self.send_admin(endpoint, ServerToAdmin::ResultAuth(None));
+    let attempts = self.auth_attempts.entry(endpoint).or_default();
+    *attempts += 1;
+    if *attempts >= self.auth_max_failures {
+      self.disconnect(endpoint);
+    }
+  }
+
+  fn bind_user(&mut self, endpoint: Endpoint, uuid: Uuid) {
+    self
+      .clients
+      .insert(endpoint, EndpointStatus::AuthedAsUser(uuid));
+    self.uuids_to_endpoints.insert(uuid, endpoint);
+    self.handler.network().send(
+      endpoint,
+      &bincode::serialize(&Message::Stc(ServerToClient::RegisterUUID(uuid))).unwrap(),
+    );
+    if self.started_at.is_some() {
+      // Сброшенный, но не подтвердивший сброс, начинает заново
+      if let Some(&msg_uuid) = self.pending_restarts.get(&uuid) {
+        return self.send_restart(endpoint, msg_uuid);
+      }
+      // Уже начавший продолжает с того же места
+      return match self.started.contains_key(&uuid) {
+        true => self.start_participant(endpoint, uuid),
+        false => self.late_join(endpoint, uuid),
+      };
+    }
+    // Пока идёт проверка готовности, новый участник в ней тоже участвует
+    if let Some(Schedule {
+      ready: Some(ready),
+      start_at: None,
+      ..
+    }) = &self.schedule
+    {
+      if !ready.contains(&uuid) {
+        self.handler.network().send(
+          endpoint,
+          &bincode::serialize(&Message::Stc(ServerToClient::ReadyCheck)).unwrap(),
+        );
+      }
+      self.check_lobby();
+    }
+  }
+
+  fn reject_user(&mut self, endpoint: Endpoint, reason: RejectReason, detail: &str) {
+    log_event!(
+      Level::Warn,
+      Msg::UserRejected,
+      endpoint = endpoint,
+      reason = format!("{:?}", reason),
+      detail = detail
+    );
+    self.handler.network().send(
+      endpoint,
+      &bincode::serialize(&Message::Stc(ServerToClient::RegisterRejected(reason))).unwrap(),
+    );
+  }
+
+  /// Сообщает участнику, что его отключили, и закрывает соединение
+  fn kick(&mut self, endpoint: Endpoint, reason: &str, banned: bool) {
+    log_event!(
+      Level::Info,
+      Msg::UserKicked,
+      endpoint = endpoint,
+      reason = reason,
+      banned = banned
+    );
+    let kicked = ServerToClient::Kicked {
+      reason: reason.to_string(),
+      banned,
     };
-    self.clients.remove(&endpoint);
+    self.handler.network().send(
+      endpoint,
+      &bincode::serialize(&Message::Stc(kicked)).unwrap(),
+    );
+    self.disconnect(endpoint);
+  }
+
+  /// Соединения участников, попадающих под `target`
+  fn participant_endpoints(&self, target: BanTarget) -> Vec<Endpoint> {
+    self
+      .clients
+      .iter()
+      .filter(|(endpoint, status)| match (status, target) {
+        (EndpointStatus::AuthedAsUser(uuid), BanTarget::Uuid(target)) => *uuid == target,
+        (EndpointStatus::AuthedAsUser(_), BanTarget::Ip(ip)) => {
+          self.peer_addr(**endpoint).ip() == ip
+        }
+        _ => false,
+      })
+      .map(|(endpoint, _)| *endpoint)
+      .collect()
+  }
+
+  /// Отключает заблокированного участника. `true`, если он заблокирован
+  fn reject_banned(&mut self, endpoint: Endpoint, uuid: Option<Uuid>) -> bool {
+    let ip = self.peer_addr(endpoint).ip();
+    let Some(ban) = self.bans.find(uuid, ip) else {
+      return false;
+    };
+    let reason = ban.reason.clone();
+    self.kick(endpoint, &reason, true);
+    true
   }
 
   fn exec_message(&mut self, endpoint: Endpoint, message: Message) {
     match message {
       Message::Stc(_) | Message::Sta(_) => {
-        println!("Невалидная категория сообщения: эндпоинт({})", endpoint)
+        log_event!(
+          Level::Warn,
+          Msg::EndpointInvalidCategory,
+          endpoint = endpoint
+        )
       }
       Message::Cts(cts_msg) => {
         self.exec_client_message(endpoint, cts_msg);
@@ -79,161 +1222,764 @@ impl ServerState {
       Message::Ats(ats_msg) => {
         self.exec_admin_message(endpoint, ats_msg);
       }
+      Message::Ping => {
+        self
+          .handler
+          .network()
+          .send(endpoint, &bincode::serialize(&Message::Pong).unwrap());
+      }
+      Message::Pong => {}
     }
   }
 
   fn exec_admin_message(&mut self, endpoint: Endpoint, message: AdminToServer) {
-    if let hogwarts_guess::AdminToServer::Auth(auth_token) = message {
-      if self.auth_token == auth_token {
-        println!("Аутефицирован: эндпоинт({})", endpoint);
-        self.clients.insert(endpoint, EndpointStatus::AuthedAsAdmin);
-        self.handler.network().send(
-          endpoint,
-          &bincode::serialize(&Message::Sta(ServerToAdmin::ResultAuth(true))).unwrap(),
+    if let hogwarts_guess::AdminToServer::AuthRequest { name } = message {
+      let ip = self.peer_addr(endpoint).ip();
+      if self.auth_lockout.locked_for(&ip, Instant::now()).is_some() {
+        return self.auth_failed(endpoint, Some(name), FailureReason::Locked);
+      }
+      let nonce = auth::new_nonce();
+      log_event!(
+        Level::Debug,
+        Msg::AdminChallenged,
+        endpoint = endpoint,
+        admin = name
+      );
+      self
+        .clients
+        .insert(endpoint, EndpointStatus::Challenged { name, nonce });
+      self.send_admin(endpoint, ServerToAdmin::Challenge(nonce));
+      return;
+    }
+    if let hogwarts_guess::AdminToServer::Auth(response) = message {
+      // Вызов одноразовый: повторить тот же ответ не получится
+      let (name, nonce) = match self.clients.insert(endpoint, EndpointStatus::JustConnected) {
+        Some(EndpointStatus::Challenged { name, nonce }) => (name, nonce),
+        _ => return self.auth_failed(endpoint, None, FailureReason::NoChallenge),
+      };
+      let account = self
+        .accounts
+        .find(&name)
+        .filter(|account| auth::verify(&account.token, &nonce, &response))
+        .cloned();
+      if let Some(account) = account {
+        self.auth_lockout.reset(&self.peer_addr(endpoint).ip());
+        self.auth_attempts.remove(&endpoint);
+        log_event!(
+          Level::Info,
+          Msg::AdminAuthed,
+          endpoint = endpoint,
+          admin = account.name,
+          role = account.role
         );
-      } else {
-        println!("Неудачно аутефицирован: эндпоинт({})", endpoint);
-        self.handler.network().send(
+        let role = account.role;
+        self.clients.insert(
           endpoint,
-          &bincode::serialize(&Message::Sta(ServerToAdmin::ResultAuth(false))).unwrap(),
+          EndpointStatus::AuthedAsAdmin {
+            name: account.name.clone(),
+            role,
+          },
         );
+        self.send_admin(endpoint, ServerToAdmin::ResultAuth(Some(role)));
+        self.send_admin(endpoint, ServerToAdmin::Experiment(self.rules.clone()));
+      } else {
+        self.auth_failed(endpoint, Some(name), FailureReason::BadResponse);
       }
       return;
     };
-    if !self
-      .clients
-      .get(&endpoint)
-      .is_some_and(|v| *v == EndpointStatus::AuthedAsAdmin)
-    {
-      return println!("Доступ к админке без аутефикации: эндпоинт({})", endpoint);
+    let (admin, role) = match self.clients.get(&endpoint) {
+      Some(EndpointStatus::AuthedAsAdmin { name, role }) => (name.clone(), *role),
+      _ => return log_event!(Level::Warn, Msg::AdminUnauthed, endpoint = endpoint),
+    };
+    let required = message.required_role();
+    if role < required {
+      log_event!(
+        Level::Warn,
+        Msg::AdminForbidden,
+        admin = admin,
+        role = role,
+        command = format!("{:?}", message)
+      );
+      self.handler.network().send(
+        endpoint,
+        &bincode::serialize(&Message::Sta(ServerToAdmin::PermissionDenied(required))).unwrap(),
+      );
+      return;
     }
     match message {
-      hogwarts_guess::AdminToServer::Start => {
-        println!("Рассылка начала игры начата");
-        for (endpoint, client) in self.clients.iter() {
-          if let EndpointStatus::AuthedAsUser { .. } = client {
-            let msg_uuid = Uuid::new_v4();
-            println!(
-              "  Отправка: эндпоинт({}) & сообщение({})",
-              endpoint, msg_uuid
-            );
-            self.handler.network().send(
-              *endpoint,
-              &bincode::serialize(&Message::Stc(ServerToClient::ExperimentStart(msg_uuid)))
-                .unwrap(),
-            );
-          }
+      hogwarts_guess::AdminToServer::Start => self.start(&admin),
+      hogwarts_guess::AdminToServer::ScheduleStart { .. } if self.started_at.is_some() => {
+        self.send_admin(endpoint, ServerToAdmin::AlreadyStarted)
+      }
+      hogwarts_guess::AdminToServer::ScheduleStart { at, ready_check } => {
+        if start_time(Utc::now(), at).is_none() {
+          return self.send_admin(endpoint, ServerToAdmin::InvalidStartTime);
         }
-        println!("Рассылка начала игры закончена")
+        self.schedule_start(admin, at, ready_check)
       }
-      hogwarts_guess::AdminToServer::Stats => {
-        println!("Отправка статистики: эндпоинт({})", endpoint);
-        self.handler.network().send(
+      hogwarts_guess::AdminToServer::CancelStart => self.cancel_start(&admin),
+      hogwarts_guess::AdminToServer::LateQueue => {
+        log_event!(Level::Debug, Msg::SendingLateQueue, admin = admin);
+        let profiles = self.enrollment.profiles(self.late_queue.iter());
+        self.send_admin(
           endpoint,
-          &bincode::serialize(&Message::Sta(ServerToAdmin::Stats(self.stat_users.clone())))
-            .unwrap(),
+          ServerToAdmin::LateQueue(self.late_queue.clone(), profiles),
         );
       }
+      hogwarts_guess::AdminToServer::AdmitLate => {
+        let admitted = self.admit_late(&admin);
+        self.send_admin(endpoint, ServerToAdmin::Admitted(admitted));
+      }
+      hogwarts_guess::AdminToServer::Restart { targets } => {
+        let restarted = self.restart(&admin, targets);
+        self.send_admin(endpoint, ServerToAdmin::Restarted(restarted));
+      }
+      hogwarts_guess::AdminToServer::Stats => {
+        log_event!(Level::Debug, Msg::SendingStats, admin = admin);
+        self.send_admin(endpoint, ServerToAdmin::Leaderboard(self.leaderboard()));
+      }
       hogwarts_guess::AdminToServer::WaitAnswers => {
-        println!("Отправка списка ожидания: эндпоинт({})", endpoint);
+        log_event!(Level::Debug, Msg::SendingWaitList, admin = admin);
         self.handler.network().send(
           endpoint,
           &bincode::serialize(&Message::Sta(ServerToAdmin::WaitAnswers(
             self.waiting_users.clone(),
+            self.enrollment.profiles(self.waiting_users.0.keys()),
           )))
           .unwrap(),
         );
       }
       hogwarts_guess::AdminToServer::SendAnswer { target, answer } => {
-        println!(
-          "Принят ответ на попытку: эндпоинт({}) & answer({:?}) @ таргет({})",
-          endpoint, answer, target
+        log_event!(
+          Level::Info,
+          Msg::AnswerReceived,
+          admin = admin,
+          answer = format!("{:?}", answer),
+          target = target
         );
+        let mut exhausted = false;
         if let Some(trg_endpoint) = self.uuids_to_endpoints.get(&target) {
           self.waiting_users.0.remove(&target);
+          if let Some(history) = self.histories.get_mut(&target) {
+            history.answer_last(answer);
+            exhausted = answer != GuessResult::Equal
+              && self
+                .rules
+                .max_attempts
+                .is_some_and(|max| history.0.len() as u64 >= max);
+          }
+          if let Some(guessed_at) = self.guessed_at.remove(&target) {
+            self.metrics.observe_answer_latency(guessed_at.elapsed());
+          }
+          self.metrics.answers_sent.inc();
           let msg_uuid = Uuid::new_v4();
-          println!(
-            "Отправка: эндпоинт({}) & сообщение({})",
-            trg_endpoint, msg_uuid
+          log_event!(
+            Level::Debug,
+            Msg::Sending,
+            endpoint = trg_endpoint,
+            message = msg_uuid
           );
           self.handler.network().send(
             *trg_endpoint,
             &bincode::serialize(&Message::Stc(ServerToClient::Answer(answer, msg_uuid))).unwrap(),
           );
         } else {
-          println!("Клиент не найден!");
+          log_event!(Level::Warn, Msg::ParticipantNotFound, target = target);
+        }
+        if exhausted {
+          self.finish(target, Outcome::OutOfAttempts);
         }
       }
-      hogwarts_guess::AdminToServer::Auth(_) => unreachable!(), // Было обработано раннее
+      hogwarts_guess::AdminToServer::AuthFailures => {
+        log_event!(Level::Debug, Msg::SendingAuthFailures, admin = admin);
+        let failures = self.auth_failures.iter().cloned().collect();
+        self.send_admin(endpoint, ServerToAdmin::AuthFailures(failures));
+      }
+      hogwarts_guess::AdminToServer::CreateInvite { label, uses } => {
+        log_event!(
+          Level::Info,
+          Msg::InviteIssued,
+          admin = admin,
+          label = label,
+          uses = uses
+        );
+        let invite = self.enrollment.invites.create(label, uses.max(1));
+        self.send_admin(endpoint, ServerToAdmin::InviteCreated(invite));
+      }
+      hogwarts_guess::AdminToServer::Invites => {
+        log_event!(Level::Debug, Msg::SendingInvites, admin = admin);
+        let invites = self.enrollment.invites.invites.clone();
+        self.send_admin(endpoint, ServerToAdmin::Invites(invites));
+      }
+      hogwarts_guess::AdminToServer::Kick { target, reason } => {
+        log_event!(
+          Level::Info,
+          Msg::KickRequested,
+          admin = admin,
+          target = target,
+          reason = reason
+        );
+        let endpoints = self.participant_endpoints(BanTarget::Uuid(target));
+        for trg_endpoint in &endpoints {
+          self.kick(*trg_endpoint, &reason, false);
+        }
+        self.send_admin(endpoint, ServerToAdmin::Kicked(endpoints.len()));
+      }
+      hogwarts_guess::AdminToServer::Ban { target, reason } => {
+        log_event!(
+          Level::Info,
+          Msg::BanAdded,
+          admin = admin,
+          target = target,
+          reason = reason
+        );
+        self.bans.add(Ban {
+          target,
+          reason: reason.clone(),
+          admin,
+          at: chrono::Utc::now(),
+        });
+        let endpoints = self.participant_endpoints(target);
+        for trg_endpoint in &endpoints {
+          self.kick(*trg_endpoint, &reason, true);
+        }
+        self.send_admin(endpoint, ServerToAdmin::Kicked(endpoints.len()));
+      }
+      hogwarts_guess::AdminToServer::Unban(target) => {
+        let removed = self.bans.remove(target);
+        log_event!(
+          Level::Info,
+          Msg::BanRemoved,
+          admin = admin,
+          target = target,
+          removed = removed
+        );
+        self.send_admin(endpoint, ServerToAdmin::Unbanned(removed));
+      }
+      hogwarts_guess::AdminToServer::Bans => {
+        log_event!(Level::Debug, Msg::SendingBans, admin = admin);
+        self.send_admin(endpoint, ServerToAdmin::Bans(self.bans.0.clone()));
+      }
+      hogwarts_guess::AdminToServer::Export => {
+        log_event!(Level::Debug, Msg::SendingExport, admin = admin);
+        self.send_admin(endpoint, ServerToAdmin::Export(self.export()));
+      }
+      hogwarts_guess::AdminToServer::Analytics => {
+        log_event!(Level::Debug, Msg::SendingAnalytics, admin = admin);
+        self.send_admin(endpoint, ServerToAdmin::Analytics(self.analytics()));
+      }
+      hogwarts_guess::AdminToServer::PublishLeaderboard => {
+        let published = self.publish_leaderboard(&admin);
+        self.send_admin(endpoint, ServerToAdmin::LeaderboardPublished(published));
+      }
+      hogwarts_guess::AdminToServer::Shutdown => self.shutdown(&admin),
+      hogwarts_guess::AdminToServer::AuthRequest { .. }
+      | hogwarts_guess::AdminToServer::Auth(_) => {
+        unreachable!() // Было обработано раннее
+      }
     }
   }
 
   fn exec_client_message(&mut self, endpoint: Endpoint, message: ClientToServer) {
     match message {
-      ClientToServer::Register => {
+      ClientToServer::Register { invite, profile } => {
+        if self.reject_banned(endpoint, None) {
+          return;
+        }
+        // Анкета проверяется раньше, чтобы не тратить код приглашения впустую
+        let profile = match profile.normalized() {
+          Ok(profile) => profile,
+          Err(err) => return self.reject_user(endpoint, RejectReason::Profile, &err),
+        };
+        let label = match invite {
+          Some(code) => match self.enrollment.invites.redeem(&code) {
+            Some(label) => Some(label),
+            None => return self.reject_user(endpoint, RejectReason::Invite, &code),
+          },
+          None if self.enrollment.required => {
+            return self.reject_user(endpoint, RejectReason::Invite, "-")
+          }
+          None => None,
+        };
         let new_uuid = Uuid::new_v4();
-        println!(
-          "Зарегистрирован юзер: эндпоинт({}) & уид({})",
-          endpoint, new_uuid
+        let invite = label.clone().unwrap_or_else(|| "-".to_string());
+        let name = self.enrollment.enroll(new_uuid, label, profile);
+        log_event!(
+          Level::Info,
+          Msg::UserRegistered,
+          endpoint = endpoint,
+          uuid = new_uuid,
+          name = name,
+          invite = invite
         );
-        self
-          .clients
-          .insert(endpoint, EndpointStatus::AuthedAsUser(new_uuid));
-        self.uuids_to_endpoints.insert(new_uuid, endpoint);
-        self.handler.network().send(
-          endpoint,
-          &bincode::serialize(&Message::Stc(ServerToClient::RegisterUUID(new_uuid))).unwrap(),
+        self.bind_user(endpoint, new_uuid);
+      }
+      ClientToServer::Resume { uuid, profile } => {
+        if self.reject_banned(endpoint, Some(uuid)) {
+          return;
+        }
+        // Без приглашения продолжить можно только уже выданный UUID
+        let known = match self.enrollment.participants.get(&uuid) {
+          Some(participant) => Some((participant.invite.clone(), participant.profile.clone())),
+          None if self.enrollment.required => {
+            return self.reject_user(endpoint, RejectReason::Invite, "-")
+          }
+          None => None,
+        };
+        let (label, profile) = match known {
+          Some(known) => known,
+          None => match profile.normalized() {
+            Ok(profile) => (None, profile),
+            Err(err) => return self.reject_user(endpoint, RejectReason::Profile, &err),
+          },
+        };
+        // Старое соединение могло ещё не дождаться таймаута молчания:
+        // переподключившийся участник его заменяет
+        if let Some(old) = self.uuids_to_endpoints.get(&uuid).copied() {
+          log_event!(
+            Level::Info,
+            Msg::UserTakenOver,
+            endpoint = endpoint,
+            uuid = uuid,
+            old_endpoint = old
+          );
+          self.disconnect(old);
+        }
+        log_event!(
+          Level::Info,
+          Msg::UserResumed,
+          endpoint = endpoint,
+          uuid = uuid
         );
+        if !self.enrollment.participants.contains_key(&uuid) {
+          self.enrollment.enroll(uuid, label, profile);
+        }
+        self.bind_user(endpoint, uuid);
       }
       ClientToServer::Guess(guess) => {
-        println!("Попытка: эндпоинт({}) & попытка({})", endpoint, guess);
+        if !self.flood.take_guess(endpoint) {
+          return self.flooded(endpoint, "guesses");
+        }
+        log_event!(
+          Level::Debug,
+          Msg::GuessReceived,
+          endpoint = endpoint,
+          guess = guess
+        );
+        let Some(EndpointStatus::AuthedAsUser(uuid)) = self.clients.get(&endpoint) else {
+          return log_event!(Level::Warn, Msg::UserNotFound, endpoint = endpoint);
+        };
+        let uuid = *uuid;
+        // В том числе опоздавшие, ждущие допуска
+        if !self.started.contains_key(&uuid) {
+          log_event!(
+            Level::Debug,
+            Msg::GuessBeforeStart,
+            endpoint = endpoint,
+            guess = guess
+          );
+          self.handler.network().send(
+            endpoint,
+            &bincode::serialize(&Message::Stc(ServerToClient::NotStarted)).unwrap(),
+          );
+          return;
+        }
+        // До подтверждения сброса приходят попытки прошлого раунда
+        if self.pending_restarts.contains_key(&uuid) {
+          return log_event!(
+            Level::Debug,
+            Msg::GuessBeforeRestart,
+            endpoint = endpoint,
+            guess = guess
+          );
+        }
+        // Выбывшему участнику сервер напоминает, почему попытка не принята
+        if let Some(outcome) = self.outcomes.get(&uuid) {
+          return self.finish(uuid, *outcome);
+        }
+        if self.time_up(&uuid) {
+          return self.finish(uuid, Outcome::TimeUp);
+        }
+        // После переподключения клиент повторяет попытку, оставшуюся без ответа
+        let resent = self
+          .histories
+          .get(&uuid)
+          .and_then(|history| history.0.last())
+          .is_some_and(|entry| entry.result.is_none() && entry.guess == guess);
+        if resent {
+          self.waiting_users.0.insert(uuid, guess);
+          self.guessed_at.entry(uuid).or_insert_with(Instant::now);
+          return;
+        }
+        // Попытки без ответа тоже расходуют лимит. Итог запишется, когда на последнюю
+        // из них ответят: она ещё может оказаться верной
+        let attempts = self.histories.get(&uuid).map_or(0, |h| h.0.len()) as u64;
+        if self.rules.max_attempts.is_some_and(|max| attempts >= max) {
+          self.handler.network().send(
+            endpoint,
+            &bincode::serialize(&Message::Stc(ServerToClient::OutOfAttempts)).unwrap(),
+          );
+          return;
+        }
+        if !self.rules.domain.contains(guess) {
+          log_event!(
+            Level::Debug,
+            Msg::GuessOutOfDomain,
+            endpoint = endpoint,
+            guess = guess
+          );
+          self.handler.network().send(
+            endpoint,
+            &bincode::serialize(&Message::Stc(ServerToClient::GuessRejected(guess))).unwrap(),
+          );
+          return;
+        }
+        self.waiting_users.0.insert(uuid, guess);
+        self.histories.entry(uuid).or_default().push_guess(guess);
+        self.guessed_at.insert(uuid, Instant::now());
+        self.metrics.guesses_received.inc();
+      }
+      ClientToServer::Ack(msg_uuid) => {
+        log_event!(Level::Debug, Msg::AckReceived, message = msg_uuid);
         if let Some(EndpointStatus::AuthedAsUser(uuid)) = self.clients.get(&endpoint) {
-          self.waiting_users.0.insert(*uuid, guess);
-          *self.stat_users.0.entry(*uuid).or_default() += 1;
-        } else {
-          println!("Не удалось найти юзера");
+          let uuid = *uuid;
+          if self.pending_restarts.get(&uuid) == Some(&msg_uuid) {
+            self.pending_restarts.remove(&uuid);
+          }
         }
       }
-      ClientToServer::Ack(uuid) => {
-        println!("Получено подтверждение: уид({})", uuid);
+      ClientToServer::Ready => {
+        let Some(EndpointStatus::AuthedAsUser(uuid)) = self.clients.get(&endpoint) else {
+          return log_event!(Level::Warn, Msg::UserNotFound, endpoint = endpoint);
+        };
+        let uuid = *uuid;
+        if let Some(Schedule {
+          ready: Some(ready), ..
+        }) = &mut self.schedule
+        {
+          log_event!(Level::Debug, Msg::ParticipantReady, target = uuid);
+          ready.insert(uuid);
+          self.check_lobby();
+        }
       }
     }
   }
 }
 
+/// Когда начать эксперимент, `None` - если момент не выразить во времени сервера
+fn start_time(now: DateTime<Utc>, at: StartAt) -> Option<DateTime<Utc>> {
+  match at {
+    StartAt::After(secs) => {
+      let delta = TimeDelta::try_seconds(i64::try_from(secs).ok()?)?;
+      now.checked_add_signed(delta)
+    }
+    StartAt::At(at) => Some(at),
+  }
+}
+
 fn main() {
-  let cli = Cli::parse();
+  let cli: Cli = i18n::parse_cli(
+    Msg::ServerName,
+    Msg::ServerAbout,
+    &[
+      ("config", Msg::CliConfig),
+      ("address", Msg::CliServerAddress),
+      ("port", Msg::CliPort),
+      ("auth_token", Msg::CliServerAuthToken),
+      ("accounts", Msg::CliAccounts),
+      ("invites", Msg::CliInvites),
+      ("require_invite", Msg::CliRequireInvite),
+      ("auth_max_failures", Msg::CliAuthMaxFailures),
+      ("auth_lockout_secs", Msg::CliAuthLockoutSecs),
+      ("auth_lockout_max_secs", Msg::CliAuthLockoutMaxSecs),
+      ("max_payload_bytes", Msg::CliMaxPayloadBytes),
+      ("max_messages_per_sec", Msg::CliMaxMessagesPerSec),
+      ("max_guesses_per_sec", Msg::CliMaxGuessesPerSec),
+      ("heartbeat_secs", Msg::CliHeartbeatSecs),
+      ("idle_timeout_secs", Msg::CliIdleTimeoutSecs),
+      ("log_level", Msg::CliLogLevel),
+      ("log_format", Msg::CliLogFormat),
+      ("log_file", Msg::CliLogFile),
+      ("metrics_address", Msg::CliMetricsAddress),
+      ("tls_cert", Msg::CliTlsCert),
+      ("tls_key", Msg::CliTlsKey),
+      ("report_file", Msg::CliReportFile),
+      ("export_dir", Msg::CliExportDir),
+      ("ranking", Msg::CliRanking),
+      ("seconds_per_guess", Msg::CliSecondsPerGuess),
+      ("publish_leaderboard", Msg::CliPublishLeaderboard),
+      ("low", Msg::CliLow),
+      ("high", Msg::CliHigh),
+      ("step", Msg::CliStep),
+      ("values", Msg::CliValues),
+      ("max_attempts", Msg::CliMaxAttempts),
+      ("time_limit_secs", Msg::CliTimeLimitSecs),
+      ("guess_time_limit_secs", Msg::CliGuessTimeLimitSecs),
+      ("late_joiners", Msg::CliLateJoiners),
+    ],
+  );
+
+  let mut config = match &cli.config {
+    Some(path) => match Config::load(path) {
+      Ok(config) => config,
+      Err(err) => return trln!(Msg::ConfigLoadFailed, err),
+    },
+    None => Config::default(),
+  };
+  cli.apply(&mut config);
+  if let Err(err) = config.validate() {
+    return trln!(Msg::ConfigInvalid, err);
+  }
+
+  let log = &config.log;
+  if let Err(err) = logging::init(log.level, log.format, log.file.as_deref()) {
+    return trln!(Msg::LogFileFailed, err);
+  }
+
+  let accounts = match config.auth.accounts {
+    Some(path) => match Accounts::load(&path) {
+      Ok(accounts) => accounts,
+      Err(err) => return trln!(Msg::AccountsLoadFailed, err),
+    },
+    None => {
+      let auth_token = config
+        .auth
+        .token
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+      // Токен не пишется в журнал, иначе он окажется в файле
+      trln!(Msg::ServerAuthToken, auth_token);
+      Accounts::single(accounts::DEFAULT_ADMIN, auth_token)
+    }
+  };
+
+  let enrollment = Enrollment {
+    invites: match config.enrollment.invites {
+      Some(path) => match Invites::load(&path) {
+        Ok(invites) => invites,
+        Err(err) => return trln!(Msg::InvitesLoadFailed, err),
+      },
+      None => Invites::default(),
+    },
+    required: config.enrollment.require_invite,
+    participants: HashMap::new(),
+  };
+
+  let tls_config = match (&config.tls.cert, &config.tls.key) {
+    (Some(cert), Some(key)) => match tls::server_config(cert, key) {
+      Ok(config) => Some(config),
+      Err(err) => return log_event!(Level::Error, Msg::TlsConfigFailed, error = err),
+    },
+    _ => None,
+  };
+  // С TLS наружу смотрит туннель, а сервер слушает только локально
+  let listen_addr: SocketAddr = match tls_config {
+    Some(_) => (Ipv4Addr::LOCALHOST, 0).into(),
+    None => (config.listen.address, config.listen.port).into(),
+  };
+  let peers = PeerAddrs::default();
 
-  let auth_token = cli.auth_token.unwrap_or_else(|| Uuid::new_v4().to_string());
-  println!("Токен аутефикации: {}", auth_token);
+  let (handler, listener) = node::split::<Signal>();
+  let (listener_id, tls_listener) =
+    match handler.network().listen(Transport::FramedTcp, listen_addr) {
+      Ok((id, real_addr)) => {
+        log_event!(Level::Info, Msg::Listening, address = real_addr, id = id);
+        let tls_listener = match tls_config {
+          Some(tls_config) => {
+            let address = (config.listen.address, config.listen.port).into();
+            match tls::serve(tls_config, address, real_addr, peers.clone()) {
+              Ok(tls_listener) => {
+                log_event!(
+                  Level::Info,
+                  Msg::TlsListening,
+                  address = tls_listener.local_addr
+                );
+                Some(tls_listener)
+              }
+              Err(err) => return log_event!(Level::Error, Msg::ListenFailed, error = err),
+            }
+          }
+          None => None,
+        };
+        (id, tls_listener)
+      }
+      Err(err) => {
+        return log_event!(
+          Level::Error,
+          Msg::ListenFailed,
+          error = format!("{:?}", err)
+        )
+      }
+    };
 
-  let (handler, listener) = node::split::<()>();
-  match handler
-    .network()
-    .listen(Transport::Tcp, (cli.address, cli.port))
-  {
-    Ok((id, real_addr)) => println!("Слушаем на {} & id({})", real_addr, id),
-    Err(err) => return println!("Не удалось открыть эндпоинт: {:?}", err),
+  let metrics = Arc::new(Metrics::default());
+  if let Some(address) = config.listen.metrics_address {
+    match metrics.clone().serve(address) {
+      Ok(real_addr) => log_event!(Level::Info, Msg::MetricsListening, address = real_addr),
+      Err(err) => return log_event!(Level::Error, Msg::MetricsFailed, error = err),
+    }
   }
 
-  let mut state = ServerState::new(auth_token, handler);
+  let lockout = Lockout::new(
+    Duration::from_secs(config.auth.lockout_secs),
+    Duration::from_secs(config.auth.lockout_max_secs),
+  );
+  let limits = &config.limits;
+  let mut state = ServerState::new(
+    accounts,
+    config.auth.max_failures,
+    lockout,
+    enrollment,
+    Flood {
+      max_payload: limits.max_payload_bytes,
+      messages_per_sec: limits.max_messages_per_sec,
+      guesses_per_sec: limits.max_guesses_per_sec,
+      buckets: HashMap::new(),
+    },
+    Heartbeat {
+      interval: Duration::from_secs(limits.heartbeat_secs),
+      idle_timeout: Duration::from_secs(limits.idle_timeout_secs),
+      last_seen: HashMap::new(),
+    },
+    handler,
+    listener_id,
+    tls_listener,
+    config.persistence.clone(),
+    config.leaderboard.clone(),
+    config.experiment.rules(),
+    config.experiment.late_joiners,
+    metrics,
+    peers,
+  );
+
+  if limits.heartbeat_secs > 0 {
+    state.handler.signals().send(Signal::Heartbeat);
+  }
+  let experiment = &config.experiment;
+  if experiment.time_limit_secs.is_some() || experiment.guess_time_limit_secs.is_some() {
+    state.handler.signals().send(Signal::Deadlines);
+  }
+
+  let signals = state.handler.clone();
+  if let Err(err) = ctrlc::set_handler(move || signals.signals().send(Signal::Shutdown)) {
+    log_event!(Level::Warn, Msg::SignalHandlerFailed, error = err);
+  }
+
+  listener.for_each(|event| {
+    match event {
+      NodeEvent::Network(event) => handle_event(&mut state, event),
+      NodeEvent::Signal(Signal::Heartbeat) => state.heartbeat(),
+      NodeEvent::Signal(Signal::Deadlines) => state.check_deadlines(),
+      NodeEvent::Signal(Signal::Countdown) => state.countdown(),
+      NodeEvent::Signal(Signal::Shutdown) => state.shutdown("signal"),
+      NodeEvent::Signal(Signal::Stop) => state.handler.stop(),
+      NodeEvent::Signal(Signal::Reconnect) => unreachable!(), // Только у клиента и админки
+    }
+    state.update_gauges();
+  });
+}
 
-  listener.for_each(|event| match event.network() {
-    message_io::network::NetEvent::Connected(_, _) => unreachable!(), // Вызывается только с клиентской стороны
-    message_io::network::NetEvent::Accepted(endpoint, _) => {
-      println!("Клиент подключился: эндпоинт({})", endpoint);
+fn handle_event(state: &mut ServerState, event: NetEvent) {
+  match event {
+    NetEvent::Connected(_, _) => unreachable!(), // Вызывается только с клиентской стороны
+    NetEvent::Accepted(endpoint, _) => {
+      log_event!(Level::Info, Msg::EndpointConnected, endpoint = endpoint);
       state.register(endpoint);
     }
-    message_io::network::NetEvent::Message(endpoint, data) => {
+    NetEvent::Message(endpoint, data) => {
+      // Соединение уже закрыто сервером, а сообщения от него ещё в очереди.
+      // После оповещения о завершении работы сообщения тоже не принимаются
+      if !state.clients.contains_key(&endpoint) || state.shutting_down {
+        return;
+      }
+      if data.len() > state.flood.max_payload {
+        return state.flooded(endpoint, "payload");
+      }
+      if !state.flood.take_message(endpoint) {
+        return state.flooded(endpoint, "messages");
+      }
+      state.heartbeat.last_seen.insert(endpoint, Instant::now());
       let msg: Message = match bincode::deserialize(data) {
-        Err(err) => return println!("Не удалось распарсить сообщение: {:?}", err),
+        Err(err) => {
+          state.metrics.parse_errors.inc();
+          return log_event!(
+            Level::Warn,
+            Msg::ParseFailed,
+            error = format!("{:?}", err),
+            endpoint = endpoint
+          );
+        }
         Ok(msg) => msg,
       };
       state.exec_message(endpoint, msg);
     }
-    message_io::network::NetEvent::Disconnected(endpoint) => {
-      println!("Клиент отключился: эндпоинт({})", endpoint);
+    NetEvent::Disconnected(endpoint) => {
+      log_event!(Level::Info, Msg::EndpointDisconnected, endpoint = endpoint);
       state.unregister(endpoint);
     }
-  });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Настройки из файла `toml`, поверх которых применены флаги `args`
+  fn configure(toml: &str, args: &[&str]) -> Config {
+    let mut config: Config = toml::from_str(toml).unwrap();
+    let args = std::iter::once("server").chain(args.iter().copied());
+    Cli::try_parse_from(args).unwrap().apply(&mut config);
+    config
+  }
+
+  #[test]
+  fn flags_override_file() {
+    let toml = "[listen]\nport = 1\n[limits]\nmax_guesses_per_sec = 2\nheartbeat_secs = 3";
+    let config = configure(toml, &["--port", "7000", "--heartbeat-secs", "4"]);
+    assert_eq!(config.listen.port, 7000);
+    assert_eq!(config.limits.heartbeat_secs, 4);
+    // Не заданное флагом остаётся из файла
+    assert_eq!(config.limits.max_guesses_per_sec, 2);
+    assert_eq!(configure("", &[]), Config::default());
+  }
+
+  #[test]
+  fn switches_only_turn_on() {
+    let toml = "[enrollment]\nrequire_invite = true";
+    assert!(configure(toml, &[]).enrollment.require_invite);
+    assert!(
+      configure("", &["--require-invite"])
+        .enrollment
+        .require_invite
+    );
+  }
+
+  #[test]
+  fn token_and_accounts_replace_each_other() {
+    let config = configure("[auth]\naccounts = \"a.toml\"", &["-t", "tok"]);
+    assert_eq!(config.auth.token.as_deref(), Some("tok"));
+    assert_eq!(config.auth.accounts, None);
+    let config = configure("[auth]\ntoken = \"tok\"", &["--accounts", "a.toml"]);
+    assert_eq!(config.auth.token, None);
+    assert_eq!(config.auth.accounts, Some(PathBuf::from("a.toml")));
+  }
+
+  #[test]
+  fn bounds_and_values_replace_each_other() {
+    let config = configure("[experiment]\nvalues = [1, 2]", &["--high", "9"]);
+    assert_eq!(config.experiment.values, None);
+    assert_eq!(config.experiment.high, Some(9));
+    assert_eq!(config.validate(), Ok(()));
+    let toml = "[experiment]\nlow = 1\nhigh = 9\nstep = 2";
+    let config = configure(toml, &["--values", "3,5"]);
+    assert_eq!(config.experiment.values, Some(vec![3, 5]));
+    assert_eq!(
+      (
+        config.experiment.low,
+        config.experiment.high,
+        config.experiment.step
+      ),
+      (None, None, None)
+    );
+    assert_eq!(config.validate(), Ok(()));
+  }
 }
@@ -0,0 +1,358 @@
+use std::{
+  fs,
+  net::{IpAddr, Ipv4Addr, SocketAddr},
+  path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::{
+  domain::Domain,
+  i18n::{self, Msg},
+  leaderboard::Ranking,
+  logging::{Format, Level},
+  rules::Rules,
+  tr,
+};
+
+/// Файл настроек сервера. Все секции и поля необязательны:
+///
+/// ```toml
+/// [listen]
+/// address = "0.0.0.0"
+/// port = 6969
+///
+/// [auth]
+/// accounts = "admins.toml"
+///
+/// [limits]
+/// max_guesses_per_sec = 2
+/// ```
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  pub listen: Listen,
+  pub tls: Tls,
+  pub auth: Auth,
+  pub enrollment: Enrollment,
+  pub limits: Limits,
+  pub log: Log,
+  pub persistence: Persistence,
+  pub leaderboard: Leaderboard,
+  pub experiment: Experiment,
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Listen {
+  pub address: IpAddr,
+  pub port: u16,
+  /// Адрес, на котором отдаются метрики Prometheus
+  pub metrics_address: Option<SocketAddr>,
+}
+
+impl Default for Listen {
+  fn default() -> Self {
+    Self {
+      address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+      port: 6969,
+      metrics_address: None,
+    }
+  }
+}
+
+/// Сертификат и ключ в PEM. Без них соединения не шифруются
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tls {
+  pub cert: Option<PathBuf>,
+  pub key: Option<PathBuf>,
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Auth {
+  /// Токен единственного админа. Без него и без `accounts` генерируется случайный
+  pub token: Option<String>,
+  /// Файл учётных записей админов
+  pub accounts: Option<PathBuf>,
+  pub max_failures: u32,
+  pub lockout_secs: u64,
+  pub lockout_max_secs: u64,
+}
+
+impl Default for Auth {
+  fn default() -> Self {
+    Self {
+      token: None,
+      accounts: None,
+      max_failures: 3,
+      lockout_secs: 1,
+      lockout_max_secs: 3600,
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Enrollment {
+  /// Файл заготовленных кодов приглашения
+  pub invites: Option<PathBuf>,
+  pub require_invite: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+  pub max_payload_bytes: usize,
+  pub max_messages_per_sec: u32,
+  pub max_guesses_per_sec: u32,
+  pub heartbeat_secs: u64,
+  pub idle_timeout_secs: u64,
+}
+
+impl Default for Limits {
+  fn default() -> Self {
+    Self {
+      max_payload_bytes: 4096,
+      max_messages_per_sec: 20,
+      max_guesses_per_sec: 5,
+      heartbeat_secs: 5,
+      idle_timeout_secs: 30,
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Log {
+  pub level: Level,
+  pub format: Format,
+  pub file: Option<PathBuf>,
+}
+
+impl Default for Log {
+  fn default() -> Self {
+    Self {
+      level: Level::Debug,
+      format: Format::Text,
+      file: None,
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Persistence {
+  /// Куда записать итоговый отчёт (JSON) при завершении работы
+  pub report_file: Option<PathBuf>,
+  /// Куда выгрузить результаты (CSV и JSON) при завершении работы
+  pub export_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Leaderboard {
+  pub ranking: Ranking,
+  /// Штраф за попытку в правиле `score`
+  pub seconds_per_guess: u64,
+  /// Разослать лидерборд участникам при завершении работы
+  pub publish_on_shutdown: bool,
+}
+
+impl Default for Leaderboard {
+  fn default() -> Self {
+    Self {
+      ranking: Ranking::Guesses,
+      seconds_per_guess: 30,
+      publish_on_shutdown: false,
+    }
+  }
+}
+
+/// Допустимые числа (границы с шагом или явный список) и ограничения участников.
+/// Без них годится любое 64-битное число, а угадывать можно сколько угодно
+#[derive(Clone, PartialEq, Eq, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Experiment {
+  pub low: Option<i64>,
+  pub high: Option<i64>,
+  pub step: Option<u64>,
+  pub values: Option<Vec<i64>>,
+  pub max_attempts: Option<u64>,
+  pub time_limit_secs: Option<u64>,
+  pub guess_time_limit_secs: Option<u64>,
+  pub late_joiners: LateJoiners,
+}
+
+/// Что делать с участником, подключившимся после старта
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Debug, Default, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum LateJoiners {
+  #[default]
+  #[value(help = i18n::text(Msg::CliLateJoinersStart))]
+  Start,
+  #[value(help = i18n::text(Msg::CliLateJoinersQueue))]
+  Queue,
+}
+
+impl Experiment {
+  pub fn rules(&self) -> Rules {
+    Rules {
+      domain: self.domain(),
+      max_attempts: self.max_attempts,
+      time_limit_secs: self.time_limit_secs,
+      guess_time_limit_secs: self.guess_time_limit_secs,
+    }
+  }
+
+  fn domain(&self) -> Domain {
+    if let Some(values) = &self.values {
+      return Domain::values(values.clone());
+    }
+    Domain::Range {
+      low: self.low.unwrap_or(i64::MIN),
+      high: self.high.unwrap_or(i64::MAX),
+      step: self.step.unwrap_or(1),
+    }
+  }
+}
+
+impl Config {
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let data = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    toml::from_str(&data).map_err(|err| err.to_string())
+  }
+
+  /// Проверяет сочетания настроек, которые по отдельности допустимы
+  pub fn validate(&self) -> Result<(), String> {
+    if self.auth.token.is_some() && self.auth.accounts.is_some() {
+      return Err(tr!(Msg::ConfigTokenWithAccounts));
+    }
+    if self.auth.token.as_deref() == Some("") {
+      return Err(tr!(Msg::ConfigTokenEmpty));
+    }
+    if self.tls.cert.is_some() != self.tls.key.is_some() {
+      return Err(tr!(Msg::ConfigTlsPair));
+    }
+    if self.auth.lockout_secs > self.auth.lockout_max_secs {
+      return Err(tr!(Msg::ConfigLockoutRange));
+    }
+    if self.limits.max_payload_bytes == 0 {
+      return Err(tr!(Msg::ConfigPayloadZero));
+    }
+    let experiment = &self.experiment;
+    if experiment.values.is_some()
+      && (experiment.low.is_some() || experiment.high.is_some() || experiment.step.is_some())
+    {
+      return Err(tr!(Msg::ConfigValuesWithRange));
+    }
+    if experiment.step == Some(0) {
+      return Err(tr!(Msg::ConfigStepZero));
+    }
+    if experiment.domain().is_empty() {
+      return Err(tr!(Msg::ConfigDomainEmpty));
+    }
+    if [
+      experiment.max_attempts,
+      experiment.time_limit_secs,
+      experiment.guess_time_limit_secs,
+    ]
+    .contains(&Some(0))
+    {
+      return Err(tr!(Msg::ConfigLimitZero));
+    }
+    // Иначе живые соединения закрывались бы раньше, чем их успеют пропинговать
+    let limits = &self.limits;
+    if limits.heartbeat_secs == 0 && limits.idle_timeout_secs > 0 {
+      return Err(tr!(Msg::ConfigIdleWithoutHeartbeat));
+    }
+    if limits.idle_timeout_secs > 0 && limits.idle_timeout_secs <= limits.heartbeat_secs {
+      return Err(tr!(Msg::ConfigIdleTooShort));
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(toml: &str) -> Config {
+    toml::from_str(toml).unwrap()
+  }
+
+  #[test]
+  fn empty_file_is_default_and_valid() {
+    let config = parse("");
+    assert_eq!(config, Config::default());
+    assert_eq!(config.validate(), Ok(()));
+    assert_eq!(config.experiment.rules().domain, Domain::default());
+  }
+
+  #[test]
+  fn unknown_fields_rejected() {
+    assert!(toml::from_str::<Config>("[limits]\nmax_guesses = 1").is_err());
+    assert!(toml::from_str::<Config>("[nope]").is_err());
+  }
+
+  #[test]
+  fn invalid_combinations() {
+    let cases = [
+      (
+        "[auth]\ntoken = \"t\"\naccounts = \"a.toml\"",
+        Msg::ConfigTokenWithAccounts,
+      ),
+      ("[auth]\ntoken = \"\"", Msg::ConfigTokenEmpty),
+      ("[tls]\ncert = \"cert.pem\"", Msg::ConfigTlsPair),
+      (
+        "[auth]\nlockout_secs = 10\nlockout_max_secs = 5",
+        Msg::ConfigLockoutRange,
+      ),
+      ("[limits]\nmax_payload_bytes = 0", Msg::ConfigPayloadZero),
+      (
+        "[experiment]\nvalues = [1, 2]\nlow = 1",
+        Msg::ConfigValuesWithRange,
+      ),
+      ("[experiment]\nstep = 0", Msg::ConfigStepZero),
+      ("[experiment]\nlow = 5\nhigh = 4", Msg::ConfigDomainEmpty),
+      ("[experiment]\nvalues = []", Msg::ConfigDomainEmpty),
+      ("[experiment]\nmax_attempts = 0", Msg::ConfigLimitZero),
+      (
+        "[limits]\nheartbeat_secs = 0\nidle_timeout_secs = 10",
+        Msg::ConfigIdleWithoutHeartbeat,
+      ),
+      (
+        "[limits]\nheartbeat_secs = 10\nidle_timeout_secs = 10",
+        Msg::ConfigIdleTooShort,
+      ),
+    ];
+    for (toml, msg) in cases {
+      assert_eq!(parse(toml).validate(), Err(tr!(msg)), "{}", toml);
+    }
+  }
+
+  #[test]
+  fn heartbeat_and_idle_timeout_can_be_disabled() {
+    let config = parse("[limits]\nheartbeat_secs = 0\nidle_timeout_secs = 0");
+    assert_eq!(config.validate(), Ok(()));
+  }
+
+  #[test]
+  fn experiment_rules() {
+    let config = parse("[experiment]\nlow = 1\nhigh = 10\nstep = 3\nmax_attempts = 4");
+    let rules = config.experiment.rules();
+    assert_eq!(
+      rules.domain,
+      Domain::Range {
+        low: 1,
+        high: 10,
+        step: 3
+      }
+    );
+    assert_eq!(rules.max_attempts, Some(4));
+    let config = parse("[experiment]\nvalues = [3, 1, 3]");
+    assert_eq!(config.experiment.rules().domain, Domain::Values(vec![1, 3]));
+  }
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{history::Interval, i18n::Msg, tr};
+
+/// Числа, которые можно загадать и предложить в эксперименте
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Domain {
+  /// От `low` до `high` включительно с шагом `step`, отсчитанным от `low`
+  Range { low: i64, high: i64, step: u64 },
+  /// Только перечисленные числа, по возрастанию без повторов
+  Values(Vec<i64>),
+}
+
+impl Default for Domain {
+  fn default() -> Self {
+    Self::Range {
+      low: i64::MIN,
+      high: i64::MAX,
+      step: 1,
+    }
+  }
+}
+
+impl Domain {
+  /// Набор чисел: упорядочивает и убирает повторы
+  pub fn values(mut values: Vec<i64>) -> Self {
+    values.sort_unstable();
+    values.dedup();
+    Self::Values(values)
+  }
+
+  pub fn contains(&self, value: i64) -> bool {
+    match self {
+      Self::Range { low, high, step } => {
+        *low <= value
+          && value <= *high
+          && (i128::from(value) - i128::from(*low)) % i128::from((*step).max(1)) == 0
+      }
+      Self::Values(values) => values.binary_search(&value).is_ok(),
+    }
+  }
+
+  /// Наименьший интервал, в котором лежат все допустимые числа
+  pub fn interval(&self) -> Interval {
+    match self {
+      Self::Range { low, high, .. } => Interval {
+        low: *low,
+        high: *high,
+      },
+      Self::Values(values) => Interval {
+        low: values.first().copied().unwrap_or(0),
+        high: values.last().copied().unwrap_or(-1),
+      },
+    }
+  }
+
+  /// Сколько чисел можно загадать
+  pub fn len(&self) -> u128 {
+    match self {
+      Self::Range { low, high, step } if low <= high => {
+        ((i128::from(*high) - i128::from(*low)) as u128) / u128::from((*step).max(1)) + 1
+      }
+      Self::Range { .. } => 0,
+      Self::Values(values) => values.len() as u128,
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Переведённое описание для участников и админов
+  pub fn describe(&self) -> String {
+    match self {
+      Self::Range { step: 0 | 1, .. } => self.interval().to_string(),
+      Self::Range { step, .. } => tr!(Msg::DomainStep, self.interval(), step),
+      Self::Values(values) => {
+        let values: Vec<_> = values.iter().map(i64::to_string).collect();
+        format!("{{{}}}", values.join(", "))
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn range_with_step() {
+    let domain = Domain::Range {
+      low: -5,
+      high: 7,
+      step: 4,
+    };
+    let values: Vec<i64> = (-10..=10).filter(|&v| domain.contains(v)).collect();
+    assert_eq!(values, [-5, -1, 3, 7]);
+    assert_eq!(domain.len(), 4);
+    // Верхняя граница не на шаге
+    let domain = Domain::Range {
+      low: 0,
+      high: 10,
+      step: 4,
+    };
+    assert!(!domain.contains(10));
+    assert_eq!(domain.len(), 3);
+  }
+
+  #[test]
+  fn whole_i64_range() {
+    let domain = Domain::default();
+    assert!(domain.contains(i64::MIN));
+    assert!(domain.contains(i64::MAX));
+    assert_eq!(domain.len(), 1 << 64);
+  }
+
+  #[test]
+  fn large_step_near_bounds() {
+    let domain = Domain::Range {
+      low: i64::MIN,
+      high: i64::MAX,
+      step: u64::MAX,
+    };
+    assert!(domain.contains(i64::MIN));
+    assert!(domain.contains(i64::MAX));
+    assert!(!domain.contains(0));
+    assert_eq!(domain.len(), 2);
+    let domain = Domain::Range {
+      low: i64::MAX - 1,
+      high: i64::MAX,
+      step: 1,
+    };
+    assert!(!domain.contains(i64::MAX - 2));
+    assert_eq!(domain.len(), 2);
+  }
+
+  #[test]
+  fn zero_step_counts_as_one() {
+    let domain = Domain::Range {
+      low: 1,
+      high: 3,
+      step: 0,
+    };
+    assert!(domain.contains(2));
+    assert_eq!(domain.len(), 3);
+  }
+
+  #[test]
+  fn empty() {
+    let inverted = Domain::Range {
+      low: 1,
+      high: 0,
+      step: 1,
+    };
+    assert!(inverted.is_empty());
+    assert!(!inverted.contains(0));
+    assert!(Domain::values(vec![]).is_empty());
+  }
+
+  #[test]
+  fn values_sorted_without_duplicates() {
+    let domain = Domain::values(vec![8, 1, 5, 1]);
+    assert_eq!(domain, Domain::Values(vec![1, 5, 8]));
+    assert!(domain.contains(5));
+    assert!(!domain.contains(4));
+    assert_eq!(domain.len(), 3);
+  }
+}
@@ -0,0 +1,148 @@
+use std::{
+  fs, io,
+  path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+  analytics::Analytics,
+  history::{History, Reset},
+  profile::Profile,
+  GuessResult, Outcome,
+};
+
+/// Итоги участника: строка таблицы `participants`
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct ParticipantRow {
+  pub uuid: Uuid,
+  pub name: String,
+  pub group: Option<String>,
+  pub student_id: Option<String>,
+  pub guesses: usize,
+  pub answered: usize,
+  pub solved: bool,
+  /// Почему выбыл, если не угадал
+  pub outcome: Option<Outcome>,
+  pub first_guess_at: Option<DateTime<Utc>>,
+  pub solved_at: Option<DateTime<Utc>>,
+  /// Сколько раз админ начинал эксперимент для участника заново
+  pub restarts: usize,
+}
+
+/// Попытка участника: строка таблицы `guesses`
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct GuessRow {
+  pub uuid: Uuid,
+  pub name: String,
+  /// Номер захода: после каждого сброса прогресса на единицу больше
+  pub round: usize,
+  /// Номер попытки в заходе, с единицы
+  pub attempt: usize,
+  pub guess: i64,
+  /// Пусто, если ответа так и не было
+  pub result: Option<GuessResult>,
+  pub guessed_at: DateTime<Utc>,
+  pub answered_at: Option<DateTime<Utc>>,
+}
+
+/// Сброс прогресса участника
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct ResetRow {
+  pub uuid: Uuid,
+  pub name: String,
+  /// Какой заход сброс завершил
+  pub round: usize,
+  pub admin: String,
+  pub at: DateTime<Utc>,
+}
+
+/// Результаты эксперимента в виде двух таблиц и сводки по ним
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub struct Export {
+  pub participants: Vec<ParticipantRow>,
+  pub guesses: Vec<GuessRow>,
+  /// Сбросы и аналитика попадают только в `results.json`
+  pub resets: Vec<ResetRow>,
+  pub analytics: Analytics,
+}
+
+impl Export {
+  /// Собирает таблицы по анкетам, историям, итогам и сбросам участников,
+  /// упорядочивая по имени. Итоги считаются по последнему заходу
+  pub fn new<'a>(
+    participants: impl Iterator<Item = (Uuid, &'a Profile, &'a History, Option<Outcome>, &'a [Reset])>,
+  ) -> Self {
+    let mut participants: Vec<_> = participants.collect();
+    participants.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    let mut export = Self::default();
+    for (uuid, profile, history, outcome, resets) in participants {
+      let solved_at = history
+        .0
+        .iter()
+        .find(|entry| entry.result == Some(GuessResult::Equal))
+        .map(|entry| entry.answered_at.unwrap_or(entry.guessed_at));
+      export.participants.push(ParticipantRow {
+        uuid,
+        name: profile.name().to_string(),
+        group: profile.group.clone(),
+        student_id: profile.student_id.clone(),
+        guesses: history.0.len(),
+        answered: history.0.iter().filter(|e| e.result.is_some()).count(),
+        solved: solved_at.is_some(),
+        outcome,
+        first_guess_at: history.0.first().map(|entry| entry.guessed_at),
+        solved_at,
+        restarts: resets.len(),
+      });
+      let rounds = resets.iter().map(|reset| &reset.history).chain([history]);
+      for (round, history) in rounds.enumerate() {
+        export
+          .guesses
+          .extend(history.0.iter().enumerate().map(|(i, entry)| GuessRow {
+            uuid,
+            name: profile.name().to_string(),
+            round: round + 1,
+            attempt: i + 1,
+            guess: entry.guess,
+            result: entry.result,
+            guessed_at: entry.guessed_at,
+            answered_at: entry.answered_at,
+          }));
+      }
+      export
+        .resets
+        .extend(resets.iter().enumerate().map(|(i, reset)| ResetRow {
+          uuid,
+          name: profile.name().to_string(),
+          round: i + 1,
+          admin: reset.admin.clone(),
+          at: reset.at,
+        }));
+    }
+    export
+  }
+
+  /// Пишет в папку `participants.csv`, `guesses.csv` и `results.json`,
+  /// возвращает пути записанных файлов
+  pub fn write(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let participants = dir.join("participants.csv");
+    write_csv(&participants, &self.participants)?;
+    let guesses = dir.join("guesses.csv");
+    write_csv(&guesses, &self.guesses)?;
+    let json = dir.join("results.json");
+    fs::write(&json, serde_json::to_string_pretty(self)?)?;
+    Ok(vec![participants, guesses, json])
+  }
+}
+
+fn write_csv<T: Serialize>(path: &Path, rows: &[T]) -> io::Result<()> {
+  let mut writer = csv::Writer::from_path(path)?;
+  for row in rows {
+    writer.serialize(row)?;
+  }
+  writer.flush()
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+/// Сигналы таймеров узлов message-io
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Signal {
+  /// Сервер рассылает пинги и закрывает молчащие соединения,
+  /// клиент и админка проверяют, жив ли сервер
+  Heartbeat,
+  /// Клиент и админка пробуют подключиться заново
+  Reconnect,
+  /// Сервер проверяет, не вышло ли у участников время
+  Deadlines,
+  /// Сервер рассылает отсчёт до начала эксперимента
+  Countdown,
+  /// Сервер получил SIGINT/SIGTERM
+  Shutdown,
+  /// Участники и админы оповещены о завершении, сервер останавливается
+  Stop,
+}
+
+/// Как часто клиент и админка проверяют, жив ли сервер,
+/// а сервер - время участников
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Пауза между попытками переподключения
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(2);
@@ -0,0 +1,167 @@
+use std::{fmt, fs, io, path::Path};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::GuessResult;
+
+/// Попытка участника и ответ на неё (если уже получен)
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct HistoryEntry {
+  pub guess: i64,
+  pub result: Option<GuessResult>,
+  pub guessed_at: DateTime<Utc>,
+  pub answered_at: Option<DateTime<Utc>>,
+}
+
+/// История попыток участника в порядке отправки
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub struct History(pub Vec<HistoryEntry>);
+
+/// Сброс прогресса участника: попытки до сброса сохраняются
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Reset {
+  pub at: DateTime<Utc>,
+  /// Админ, сбросивший прогресс
+  pub admin: String,
+  pub history: History,
+}
+
+impl History {
+  /// Загружает историю из файла, отсутствующий файл считается пустой историей
+  pub fn load(path: &Path) -> io::Result<Self> {
+    match fs::read(path) {
+      Ok(data) => {
+        bincode::deserialize(&data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+      }
+      Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(err) => Err(err),
+    }
+  }
+
+  pub fn save(&self, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let data =
+      bincode::serialize(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(path, data)
+  }
+
+  pub fn push_guess(&mut self, guess: i64) {
+    self.0.push(HistoryEntry {
+      guess,
+      result: None,
+      guessed_at: Utc::now(),
+      answered_at: None,
+    });
+  }
+
+  /// Записывает ответ к последней попытке, если она ещё без ответа.
+  /// Сервер ждёт ответа только на последнюю попытку участника, более ранние
+  /// без ответа так и остаются без него
+  pub fn answer_last(&mut self, result: GuessResult) -> Option<&HistoryEntry> {
+    let entry = self.0.last_mut().filter(|e| e.result.is_none())?;
+    entry.result = Some(result);
+    entry.answered_at = Some(Utc::now());
+    Some(entry)
+  }
+
+  /// Часть интервала `range`, которая остаётся после всех полученных ответов
+  pub fn interval(&self, range: Interval) -> Interval {
+    let mut interval = range;
+    for entry in &self.0 {
+      if let Some(result) = entry.result {
+        interval.narrow(entry.guess, result);
+      }
+    }
+    interval
+  }
+}
+
+/// Интервал, в котором ещё может находиться загаданное число (границы включительно)
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Interval {
+  pub low: i64,
+  pub high: i64,
+}
+
+impl Default for Interval {
+  fn default() -> Self {
+    Self {
+      low: i64::MIN,
+      high: i64::MAX,
+    }
+  }
+}
+
+impl Interval {
+  pub fn narrow(&mut self, guess: i64, result: GuessResult) {
+    match result {
+      GuessResult::Equal => {
+        self.low = guess;
+        self.high = guess;
+      }
+      GuessResult::Less => self.high = self.high.min(guess.saturating_sub(1)),
+      GuessResult::More => self.low = self.low.max(guess.saturating_add(1)),
+    }
+  }
+
+  /// Пустой интервал означает противоречивые ответы
+  pub fn is_empty(&self) -> bool {
+    self.low > self.high
+  }
+
+  pub fn contains(&self, value: i64) -> bool {
+    self.low <= value && value <= self.high
+  }
+}
+
+impl fmt::Display for Interval {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.is_empty() {
+      return write!(f, "∅");
+    }
+    match self.low {
+      i64::MIN => write!(f, "(-∞; ")?,
+      low => write!(f, "[{}; ", low)?,
+    }
+    match self.high {
+      i64::MAX => write!(f, "+∞)"),
+      high => write!(f, "{}]", high),
+    }
+  }
+}
+
+/// Заготовки для тестов модулей, разбирающих истории
+#[cfg(test)]
+pub(crate) mod fixtures {
+  use chrono::Utc;
+
+  use super::{History, HistoryEntry, Interval};
+  use crate::{domain::Domain, GuessResult};
+
+  /// Числа от 1 до 100
+  pub const DOMAIN: Domain = Domain::Range {
+    low: 1,
+    high: 100,
+    step: 1,
+  };
+
+  pub const RANGE: Interval = Interval { low: 1, high: 100 };
+
+  /// История из попыток и ответов на них
+  pub fn history(guesses: &[(i64, Option<GuessResult>)]) -> History {
+    History(
+      guesses
+        .iter()
+        .map(|&(guess, result)| HistoryEntry {
+          guess,
+          result,
+          guessed_at: Utc::now(),
+          answered_at: result.map(|_| Utc::now()),
+        })
+        .collect(),
+    )
+  }
+}
@@ -0,0 +1,1273 @@
+use std::{env, ffi::OsString, fmt, str::FromStr, sync::OnceLock};
+
+use clap::{Arg, ArgAction, Parser};
+
+/// Язык сообщений
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Lang {
+  Ru,
+  En,
+}
+
+impl FromStr for Lang {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let s = s.to_lowercase();
+    if s.starts_with("ru") {
+      Ok(Lang::Ru)
+    } else if s.starts_with("en") {
+      Ok(Lang::En)
+    } else {
+      Err(s)
+    }
+  }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Текущий язык; до выбора языка используется русский
+pub fn lang() -> Lang {
+  *LANG.get().unwrap_or(&Lang::Ru)
+}
+
+/// Выбирает язык: `--lang`, затем `HOGWARTS_LANG`, затем локаль системы
+pub fn detect_lang(args: &[OsString]) -> Lang {
+  let mut args = args.iter().filter_map(|a| a.to_str());
+  while let Some(arg) = args.next() {
+    let value = match arg.strip_prefix("--lang") {
+      Some("") => args.next(),
+      Some(rest) => rest.strip_prefix('='),
+      None => None,
+    };
+    if let Some(lang) = value.and_then(|v| v.parse().ok()) {
+      return lang;
+    }
+  }
+  ["HOGWARTS_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+    .iter()
+    .filter_map(|var| env::var(var).ok())
+    .find_map(|v| v.parse().ok())
+    .unwrap_or(Lang::Ru)
+}
+
+/// Разбирает аргументы командной строки, переводя справку на выбранный язык.
+/// `help` сопоставляет идентификаторам аргументов их описание
+pub fn parse_cli<T: Parser>(name: Msg, about: Msg, help: &[(&str, Msg)]) -> T {
+  let args: Vec<OsString> = env::args_os().collect();
+  let _ = LANG.set(detect_lang(&args));
+
+  let template = format!(
+    "{{name}} {{version}}\n{{about-with-newline}}\n{}: {{usage}}\n\n{}:\n{{options}}",
+    text(Msg::CliUsage),
+    text(Msg::CliOptions)
+  );
+  let mut cmd = T::command()
+    .name(text(name))
+    .about(text(about))
+    .help_template(template)
+    .disable_help_flag(true)
+    .disable_version_flag(true)
+    .arg(
+      Arg::new("lang")
+        .long("lang")
+        .value_name("LANG")
+        .value_parser(["ru", "en"])
+        .help(text(Msg::CliLang)),
+    )
+    .arg(
+      Arg::new("help")
+        .short('h')
+        .long("help")
+        .action(ArgAction::Help)
+        .help(text(Msg::CliHelp)),
+    )
+    .arg(
+      Arg::new("version")
+        .short('V')
+        .long("version")
+        .action(ArgAction::Version)
+        .help(text(Msg::CliVersion)),
+    );
+  for (id, msg) in help {
+    cmd = cmd.mut_arg(*id, |arg| arg.help(text(*msg)));
+  }
+  T::from_arg_matches(&cmd.get_matches_from(args)).unwrap_or_else(|err| err.exit())
+}
+
+/// Текст сообщения на текущем языке
+pub fn text(msg: Msg) -> &'static str {
+  let (ru, en) = msg.texts();
+  match lang() {
+    Lang::Ru => ru,
+    Lang::En => en,
+  }
+}
+
+/// Подставляет аргументы вместо `{}` в шаблоне по порядку
+pub fn format(template: &str, args: &[&dyn fmt::Display]) -> String {
+  let mut out = String::with_capacity(template.len());
+  let mut args = args.iter();
+  let mut parts = template.split("{}");
+  if let Some(first) = parts.next() {
+    out.push_str(first);
+  }
+  for part in parts {
+    if let Some(arg) = args.next() {
+      out.push_str(&arg.to_string());
+    }
+    out.push_str(part);
+  }
+  out
+}
+
+/// Переведённое сообщение с подставленными аргументами
+#[macro_export]
+macro_rules! tr {
+  ($msg:expr) => {
+    $crate::i18n::text($msg).to_string()
+  };
+  ($msg:expr, $($arg:expr),+ $(,)?) => {
+    $crate::i18n::format($crate::i18n::text($msg), &[$(&$arg),+])
+  };
+}
+
+/// `println!` для переведённого сообщения
+#[macro_export]
+macro_rules! trln {
+  ($($arg:tt)+) => {
+    println!("{}", $crate::tr!($($arg)+))
+  };
+}
+
+/// Каталог всех сообщений, которые видит пользователь
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Msg {
+  // Командная строка
+  CliUsage,
+  CliOptions,
+  CliLang,
+  CliHelp,
+  CliVersion,
+  CliAddress,
+  CliServerAddress,
+  CliConfig,
+  CliPort,
+  CliAuthToken,
+  CliServerAuthToken,
+  CliAccounts,
+  CliInvites,
+  CliRequireInvite,
+  CliAdminName,
+  CliAuthMaxFailures,
+  CliAuthLockoutSecs,
+  CliAuthLockoutMaxSecs,
+  CliMaxPayloadBytes,
+  CliMaxMessagesPerSec,
+  CliMaxGuessesPerSec,
+  CliHeartbeatSecs,
+  CliIdleTimeoutSecs,
+  CliServerTimeoutSecs,
+  CliReconnectAttempts,
+  CliLogLevel,
+  CliLogFormat,
+  CliLogFormatText,
+  CliLogFormatJson,
+  CliLogFile,
+  CliMetricsAddress,
+  CliTlsCert,
+  CliTlsKey,
+  CliReportFile,
+  CliExportDir,
+  CliRanking,
+  CliRankingGuesses,
+  CliRankingTime,
+  CliRankingScore,
+  CliSecondsPerGuess,
+  CliPublishLeaderboard,
+  CliLow,
+  CliHigh,
+  CliStep,
+  CliValues,
+  CliMaxAttempts,
+  CliTimeLimitSecs,
+  CliGuessTimeLimitSecs,
+  CliLateJoiners,
+  CliLateJoinersStart,
+  CliLateJoinersQueue,
+  CliTls,
+  CliCaCert,
+  CliHistoryDir,
+  CliResume,
+  CliInvite,
+  CliDisplayName,
+  CliGroup,
+  CliStudentId,
+  CliStdinScript,
+  CliGuesses,
+  ServerName,
+  ServerAbout,
+  ClientName,
+  ClientAbout,
+  AdminName,
+  AdminAbout,
+
+  // Общие
+  Connected,
+  Authenticating,
+  ConnectFailed,
+  TlsConnectFailed,
+  InvalidCategory,
+  ParseFailed,
+  ConnectionLost,
+  Reconnecting,
+  ServerShutdown,
+  StartCancelled,
+  LeaderboardHeader,
+  RankingGuesses,
+  RankingTime,
+  RankingScore,
+  DomainStep,
+  DomainLine,
+  RulesMaxAttempts,
+  RulesTimeLimit,
+  RulesGuessTimeLimit,
+  OutcomeOutOfAttempts,
+  OutcomeTimeUp,
+  Error,
+  InvalidCommand,
+  Prompt,
+
+  // Клиент
+  ClientWelcome,
+  ParticipantToken,
+  RegisterRejected,
+  ProfileRejected,
+  Kicked,
+  Banned,
+  SolvedHeader,
+  UnsolvedHeader,
+  LeaderboardYou,
+  GuessRejected,
+  OutOfAttempts,
+  TimeUp,
+  HistoryLoaded,
+  HistoryLoadFailed,
+  HistorySaveFailed,
+  ExperimentStarted,
+  ExperimentRestarted,
+  ReadyCheckPrompt,
+  WaitingOthers,
+  CountdownTick,
+  LateQueued,
+  NotStarted,
+  AnswerFor,
+  Answer,
+  IntervalLine,
+  HistoryHeader,
+  HistoryLine,
+  AwaitingAnswer,
+  ResultEqual,
+  ResultLess,
+  ResultMore,
+  Solved,
+  GuessesExhausted,
+  GuessPrompt,
+  InvalidCommandLine,
+
+  // Админка
+  AdminWelcome,
+  UuidPrompt,
+  UuidsPrompt,
+  AnswerPrompt,
+  InvalidSymbol,
+  AdminSolvedHeader,
+  AdminUnsolvedHeader,
+  LeaderboardPublished,
+  AnalyticsHeader,
+  StrategyBinary,
+  StrategyLinear,
+  StrategyRandom,
+  AnalyticsSolved,
+  AnalyticsAttempts,
+  AnalyticsOptimal,
+  AnalyticsHistogram,
+  AnalyticsOutside,
+  AnalyticsFirstGuesses,
+  WaitHeader,
+  LateQueueHeader,
+  AuthSucceeded,
+  AuthFailed,
+  AuthLocked,
+  AuthFailuresHeader,
+  AuthFailureLine,
+  PermissionDenied,
+  InviteLabelPrompt,
+  InviteUsesPrompt,
+  InviteCreated,
+  InvitesHeader,
+  BanTargetPrompt,
+  BanTargetInvalid,
+  ReasonPrompt,
+  KickedCount,
+  Unbanned,
+  UnbanMissing,
+  ShutdownConfirm,
+  StartAtPrompt,
+  ReadyCheckConfirm,
+  LobbyStatus,
+  StartScheduledAt,
+  LateJoinStarted,
+  LateJoinQueued,
+  LateAdmitted,
+  AlreadyStarted,
+  InvalidStartTime,
+  StartDelayTooLong,
+  StartTimeSkipped,
+  RestartedCount,
+  ExportDirPrompt,
+  ExportSaved,
+  ExportFailed,
+  BansHeader,
+
+  // Сервер
+  ServerAuthToken,
+  ConfigLoadFailed,
+  ConfigInvalid,
+  ConfigTokenWithAccounts,
+  ConfigTokenEmpty,
+  ConfigTlsPair,
+  ConfigLockoutRange,
+  ConfigPayloadZero,
+  ConfigValuesWithRange,
+  ConfigStepZero,
+  ConfigDomainEmpty,
+  ConfigLimitZero,
+  ConfigIdleWithoutHeartbeat,
+  ConfigIdleTooShort,
+  AccountsLoadFailed,
+  AccountEmpty,
+  AccountDuplicate,
+  InvitesLoadFailed,
+  InviteEmpty,
+  InviteDuplicate,
+  LogFileFailed,
+  Listening,
+  TlsListening,
+  TlsConfigFailed,
+  MetricsListening,
+  MetricsFailed,
+  ListenFailed,
+  SignalHandlerFailed,
+  ShuttingDown,
+  FinalStats,
+  ReportWritten,
+  ReportFailed,
+  ResultsExported,
+  PublishingLeaderboard,
+  ResultsExportFailed,
+  SendingExport,
+  SendingAnalytics,
+  EndpointConnected,
+  EndpointDisconnected,
+  EndpointInvalidCategory,
+  AdminChallenged,
+  AdminAuthed,
+  AdminAuthFailed,
+  AdminUnauthed,
+  AdminForbidden,
+  AuthLockout,
+  EndpointDropped,
+  EndpointIdle,
+  FloodDetected,
+  SendingAuthFailures,
+  StartBroadcastBegin,
+  StartBroadcastEnd,
+  StartScheduling,
+  ScheduleCancelled,
+  CountdownStarted,
+  ParticipantReady,
+  LateJoiner,
+  AdmittingLate,
+  ParticipantRestarted,
+  Sending,
+  SendingStats,
+  SendingWaitList,
+  SendingLateQueue,
+  AnswerReceived,
+  ParticipantNotFound,
+  UserRegistered,
+  UserRejected,
+  ProfileName,
+  ProfileGroup,
+  ProfileStudentId,
+  ProfileFieldEmpty,
+  ProfileFieldTooLong,
+  ProfileFieldControl,
+  InviteIssued,
+  SendingInvites,
+  UserKicked,
+  KickRequested,
+  BanAdded,
+  BanRemoved,
+  SendingBans,
+  UserTakenOver,
+  UserResumed,
+  GuessReceived,
+  GuessOutOfDomain,
+  GuessBeforeStart,
+  GuessBeforeRestart,
+  ParticipantFinished,
+  UserNotFound,
+  AckReceived,
+}
+
+impl Msg {
+  /// Тексты сообщения: (русский, английский)
+  fn texts(self) -> (&'static str, &'static str) {
+    use Msg::*;
+    match self {
+      CliUsage => ("Использование", "Usage"),
+      CliOptions => ("Параметры", "Options"),
+      CliLang => ("Язык сообщений (ru, en)", "Message language (ru, en)"),
+      CliHelp => ("Показать справку", "Print help"),
+      CliVersion => ("Показать версию", "Print version"),
+      CliAddress => ("Адрес сервера", "Server address"),
+      CliServerAddress => ("Адрес, на котором слушает сервер", "Address to listen on"),
+      CliConfig => (
+        "Файл настроек сервера (TOML), флаги командной строки важнее него",
+        "Server configuration file (TOML), command line flags override it",
+      ),
+      CliPort => ("Порт сервера", "Server port"),
+      CliAuthToken => ("Токен аутентификации админа", "Admin authentication token"),
+      CliServerAuthToken => (
+        "Токен аутентификации админов (если не указан, генерируется случайный)",
+        "Admin authentication token (a random one is generated if omitted)",
+      ),
+      CliAccounts => (
+        "Файл учётных записей админов с ролями (TOML), вместо единого токена",
+        "Admin accounts file with roles (TOML), instead of a single token",
+      ),
+      CliInvites => (
+        "Файл с заранее выданными кодами приглашений (TOML)",
+        "File with pre-issued invite codes (TOML)",
+      ),
+      CliRequireInvite => (
+        "Регистрировать участников только по коду приглашения",
+        "Register participants only with an invite code",
+      ),
+      CliAdminName => ("Имя учётной записи админа", "Admin account name"),
+      CliAuthMaxFailures => (
+        "После скольких неудачных аутентификаций подряд закрывать соединение",
+        "Close the connection after this many failed authentications in a row",
+      ),
+      CliAuthLockoutSecs => (
+        "Блокировка адреса после первой неудачной аутентификации (секунды), удваивается с каждой следующей",
+        "Address lockout after the first failed authentication (seconds), doubles with each next one",
+      ),
+      CliAuthLockoutMaxSecs => (
+        "Наибольшая длительность блокировки адреса (секунды)",
+        "Longest address lockout (seconds)",
+      ),
+      CliMaxPayloadBytes => (
+        "Наибольший размер сообщения (байты), больше - соединение закрывается",
+        "Largest message size (bytes); larger ones close the connection",
+      ),
+      CliMaxMessagesPerSec => (
+        "Сколько сообщений в секунду можно слать с одного соединения (0 - без ограничения)",
+        "Messages per second allowed from one connection (0 - unlimited)",
+      ),
+      CliMaxGuessesPerSec => (
+        "Сколько попыток в секунду может прислать участник (0 - без ограничения)",
+        "Guesses per second allowed from one participant (0 - unlimited)",
+      ),
+      CliHeartbeatSecs => (
+        "Как часто слать пинги всем соединениям (секунды, 0 - не слать)",
+        "How often to ping every connection (seconds, 0 - never)",
+      ),
+      CliIdleTimeoutSecs => (
+        "Закрывать соединения, молчащие дольше этого (секунды, 0 - не закрывать)",
+        "Close connections silent for longer than this (seconds, 0 - never)",
+      ),
+      CliServerTimeoutSecs => (
+        "Считать сервер недоступным, если он молчит дольше этого (секунды, 0 - не проверять)",
+        "Consider the server gone if it is silent for longer than this (seconds, 0 - never)",
+      ),
+      CliReconnectAttempts => (
+        "Сколько раз подряд пробовать переподключиться к серверу",
+        "How many times in a row to try reconnecting to the server",
+      ),
+      CliLogLevel => (
+        "Самый подробный уровень журнала, который будет записан",
+        "Most verbose log level to record",
+      ),
+      CliLogFormat => ("Формат записей журнала", "Log record format"),
+      CliLogFormatText => (
+        "Строка на запись: время, уровень и переведённое сообщение",
+        "One line per record: time, level and the translated message",
+      ),
+      CliLogFormatJson => (
+        "JSON на запись: время, уровень, имя события, сообщение и все поля",
+        "One JSON object per record: time, level, event name, message and all fields",
+      ),
+      CliLogFile => (
+        "Файл журнала (по умолчанию - stdout)",
+        "Log file (stdout by default)",
+      ),
+      CliMetricsAddress => (
+        "Адрес HTTP-эндпоинта с метриками в формате Prometheus (например, 127.0.0.1:9100)",
+        "Address of the Prometheus-style HTTP metrics endpoint (e.g. 127.0.0.1:9100)",
+      ),
+      CliTlsCert => (
+        "Цепочка сертификатов сервера (PEM). Вместе с --tls-key включает TLS",
+        "Server certificate chain (PEM). Enables TLS together with --tls-key",
+      ),
+      CliTlsKey => ("Закрытый ключ сервера (PEM)", "Server private key (PEM)"),
+      CliRanking => (
+        "Как упорядочивать угадавших в лидерборде",
+        "How to rank participants who solved it",
+      ),
+      CliRankingGuesses => (
+        "Меньше попыток, при равенстве - быстрее",
+        "Fewer guesses, then faster",
+      ),
+      CliRankingTime => (
+        "Быстрее, при равенстве - меньше попыток",
+        "Faster, then fewer guesses",
+      ),
+      CliRankingScore => (
+        "Меньше время плюс штраф за каждую попытку",
+        "Lowest time plus a penalty per guess",
+      ),
+      CliSecondsPerGuess => (
+        "Штраф за попытку в правиле score, секунды",
+        "Per-guess penalty for the score ranking, seconds",
+      ),
+      CliPublishLeaderboard => (
+        "Разослать лидерборд участникам при завершении работы",
+        "Push the leaderboard to participants on shutdown",
+      ),
+      CliLow => (
+        "Наименьшее допустимое число",
+        "Smallest allowed number",
+      ),
+      CliHigh => ("Наибольшее допустимое число", "Largest allowed number"),
+      CliStep => (
+        "Шаг между допустимыми числами, от наименьшего",
+        "Step between allowed numbers, counted from the smallest",
+      ),
+      CliValues => (
+        "Список допустимых чисел через запятую вместо границ",
+        "Comma-separated allowed numbers instead of bounds",
+      ),
+      CliMaxAttempts => ("Сколько попыток у участника", "Guesses allowed per participant"),
+      CliTimeLimitSecs => (
+        "Сколько секунд у участника на весь эксперимент",
+        "Seconds each participant has for the whole experiment",
+      ),
+      CliGuessTimeLimitSecs => (
+        "Сколько секунд у участника на попытку после ответа",
+        "Seconds a participant has for each guess after an answer",
+      ),
+      CliLateJoiners => (
+        "Подключившихся после старта сразу запускать (start) или ставить в очередь (queue)",
+        "Start participants who join after the start right away (start) or queue them (queue)",
+      ),
+      CliLateJoinersStart => (
+        "Сразу начать эксперимент и для опоздавшего",
+        "Start the experiment for the late participant right away",
+      ),
+      CliLateJoinersQueue => (
+        "Ждать, пока админ не допустит",
+        "Wait until the admin lets them in",
+      ),
+      CliExportDir => (
+        "Папка, в которую при завершении работы выгружаются результаты (CSV и JSON)",
+        "Directory to export the results to on shutdown (CSV and JSON)",
+      ),
+      CliReportFile => (
+        "Файл, в который при завершении работы пишется итоговый отчёт (JSON)",
+        "File to write the final report to on shutdown (JSON)",
+      ),
+      CliTls => (
+        "Подключаться к серверу по TLS",
+        "Connect to the server over TLS",
+      ),
+      CliCaCert => (
+        "Корневой сертификат (PEM), которым проверяется сервер, например самоподписанный",
+        "Root certificate (PEM) to verify the server with, e.g. a self-signed one",
+      ),
+      CliHistoryDir => (
+        "Папка, в которой хранится история попыток каждого участника",
+        "Directory holding the guess history of each participant",
+      ),
+      CliResume => (
+        "Продолжить участие с ранее выданным токеном",
+        "Resume participation with a previously issued token",
+      ),
+      CliInvite => (
+        "Код приглашения для регистрации",
+        "Invite code to register with",
+      ),
+      CliDisplayName => (
+        "Имя, под которым участника видят админы",
+        "Name the admins see the participant under",
+      ),
+      CliGroup => ("Учебная группа", "Study group"),
+      CliStudentId => ("Номер студенческого билета", "Student ID"),
+      CliStdinScript => (
+        "Машинный режим: команды по одной на строку из stdin, события в JSON по одному на строку",
+        "Machine mode: one command per line from stdin, one JSON event per line out",
+      ),
+      CliGuesses => (
+        "Машинный режим: отправить заданные предположения по очереди, пока не будет угадано",
+        "Machine mode: send the given guesses in turn until the number is found",
+      ),
+      ServerName => (
+        "'Хогвартс Лабораторис' сервер",
+        "'Hogwarts Laboratories' server",
+      ),
+      ServerAbout => (
+        "Сервер эксперимента о угадывании чисел",
+        "Server of the number guessing experiment",
+      ),
+      ClientName => (
+        "'Хогвартс Лабораторис' клиент",
+        "'Hogwarts Laboratories' client",
+      ),
+      ClientAbout => (
+        "Позволяет участвовать в эксперименте о угадывании чисел",
+        "Take part in the number guessing experiment",
+      ),
+      AdminName => (
+        "'Хогвартс Лабораторис' админка",
+        "'Hogwarts Laboratories' admin",
+      ),
+      AdminAbout => (
+        "Позволяет управлять сервером",
+        "Control the experiment server",
+      ),
+
+      Connected => (
+        "Подключено: клиент({}) -> сервер({})",
+        "Connected: client({}) -> server({})",
+      ),
+      Authenticating => ("Аутентификация...", "Authenticating..."),
+      ConnectFailed => (
+        "Не удалось подключить: клиент({}) -> сервер({})",
+        "Failed to connect: client({}) -> server({})",
+      ),
+      TlsConnectFailed => (
+        "Не удалось установить TLS-соединение: сервер({}) & ошибка({})",
+        "Failed to establish TLS connection: server({}) & error({})",
+      ),
+      InvalidCategory => (
+        "Невалидная категория сообщения!",
+        "Invalid message category!",
+      ),
+      ParseFailed => (
+        "Не удалось распарсить сообщение: {}",
+        "Failed to parse message: {}",
+      ),
+      ConnectionLost => ("Подключение потеряно!", "Connection lost!"),
+      LeaderboardHeader => ("\nЛидерборд ({}):", "\nLeaderboard ({}):"),
+      RankingGuesses => ("меньше попыток", "fewest guesses"),
+      RankingTime => ("быстрее", "fastest"),
+      DomainStep => ("{} с шагом {}", "{} in steps of {}"),
+      DomainLine => ("Допустимые числа: {}", "Allowed numbers: {}"),
+      RulesMaxAttempts => ("Попыток: не больше {}", "Guesses: at most {}"),
+      RulesTimeLimit => ("Время на эксперимент: {}", "Time for the experiment: {}"),
+      RulesGuessTimeLimit => (
+        "Время на попытку после ответа: {} с",
+        "Time per guess after an answer: {} s",
+      ),
+      OutcomeOutOfAttempts => ("попытки кончились", "out of attempts"),
+      OutcomeTimeUp => ("время вышло", "time is up"),
+      RankingScore => (
+        "время плюс {} с за попытку",
+        "time plus {} s per guess",
+      ),
+      ServerShutdown => (
+        "Сервер завершает работу",
+        "The server is shutting down",
+      ),
+      StartCancelled => (
+        "Назначенный старт отменён",
+        "The scheduled start was cancelled",
+      ),
+      Reconnecting => (
+        "Связь с сервером потеряна, переподключение (попытка {} из {})...",
+        "Lost the server, reconnecting (attempt {} of {})...",
+      ),
+      Error => ("Ошибка: {}", "Error: {}"),
+      InvalidCommand => ("Некорректная команда!", "Invalid command!"),
+      Prompt => ("> ", "> "),
+
+      ClientWelcome => (
+        "Добро пожаловать!\
+        \n'g' - отправить предположение\
+        \n'h' - посмотреть историю ответов",
+        "Welcome!\
+        \n'g' - send a guess\
+        \n'h' - show answer history",
+      ),
+      ParticipantToken => ("Токен участника: {}", "Participant token: {}"),
+      RegisterRejected => (
+        "Регистрация отклонена: нужен действующий код приглашения (--invite)",
+        "Registration rejected: a valid invite code is required (--invite)",
+      ),
+      Kicked => (
+        "\nВас отключил админ: {}",
+        "\nYou were disconnected by an admin: {}",
+      ),
+      Banned => ("\nВы заблокированы: {}", "\nYou are banned: {}"),
+      SolvedHeader => (
+        "Угадали (место, участник, попыток, время):",
+        "Solved (rank, participant, guesses, time):",
+      ),
+      UnsolvedHeader => (
+        "Не угадали (участник, попыток, итог):",
+        "Not solved (participant, guesses, outcome):",
+      ),
+      LeaderboardYou => ("<- вы", "<- you"),
+      ProfileRejected => (
+        "Регистрация отклонена: заданное имя не должно быть пустым, а имя, группа и номер - длиннее {} символов и с управляющими символами",
+        "Registration rejected: a given name must not be empty, and name, group and ID must be at most {} characters, without control characters",
+      ),
+      HistoryLoaded => (
+        "Загружена история: {} попыток",
+        "History loaded: {} guesses",
+      ),
+      HistoryLoadFailed => (
+        "Не удалось загрузить историю: {}",
+        "Failed to load history: {}",
+      ),
+      HistorySaveFailed => (
+        "Не удалось сохранить историю: {}",
+        "Failed to save history: {}",
+      ),
+      ExperimentStarted => ("Начало эксперимента!", "The experiment has started!"),
+      ExperimentRestarted => (
+        "\nАдмин начал эксперимент для вас заново, прежние попытки не в счёт",
+        "\nThe admin restarted the experiment for you, earlier guesses no longer count",
+      ),
+      ReadyCheckPrompt => (
+        "\nАдмин проверяет готовность. Нажмите Enter, когда будете готовы",
+        "\nThe admin is checking readiness. Press Enter when you are ready",
+      ),
+      WaitingOthers => (
+        "Ждём готовности остальных...",
+        "Waiting for the others to get ready...",
+      ),
+      CountdownTick => ("До начала: {} с", "Starting in {} s"),
+      LateQueued => (
+        "Эксперимент уже идёт. Подождите, пока админ вас допустит",
+        "The experiment is already running. Wait until the admin lets you in",
+      ),
+      NotStarted => (
+        "\nЭксперимент для вас ещё не начался, предположение не принято",
+        "\nThe experiment has not started for you yet, the guess was not accepted",
+      ),
+      OutOfAttempts => (
+        "\nПопытки кончились, больше предположений не принимается",
+        "\nOut of attempts, no more guesses are accepted",
+      ),
+      TimeUp => (
+        "\nВремя вышло, больше предположений не принимается",
+        "\nTime is up, no more guesses are accepted",
+      ),
+      GuessRejected => (
+        "\nПредположение {} не принято: допустимые числа {}",
+        "\nGuess {} rejected: allowed numbers are {}",
+      ),
+      AnswerFor => ("Результаты попытки {}: {}", "Result of guess {}: {}"),
+      Answer => ("Результаты попытки: {}", "Result of guess: {}"),
+      IntervalLine => ("Интервал: {}", "Interval: {}"),
+      HistoryHeader => ("История:", "History:"),
+      HistoryLine => ("  {} :: {} -> {}", "  {} :: {} -> {}"),
+      AwaitingAnswer => ("ожидает ответа", "awaiting answer"),
+      ResultEqual => ("равно", "equal"),
+      ResultLess => ("меньше", "less"),
+      ResultMore => ("больше", "more"),
+      Solved => ("Число угадано!", "The number is found!"),
+      GuessesExhausted => ("Предположения закончились", "No guesses left"),
+      GuessPrompt => ("Предположение: ", "Guess: "),
+      InvalidCommandLine => ("Некорректная команда: {}", "Invalid command: {}"),
+
+      AdminWelcome => (
+        "Добро пожаловать!\
+        \n's' - начать эксперимент\
+        \n'd' - назначить старт с отсчётом\
+        \n'c' - отменить назначенный старт\
+        \n'q' - показать ждущих допуска\
+        \n'j' - допустить опоздавших\
+        \n'z' - начать заново для участников\
+        \n'a' - ответить участнику\
+        \n'l' - показать лидерборду\
+        \n'w' - показать ожидающих\
+        \n'f' - показать неудачные входы\
+        \n'i' - выдать код приглашения\
+        \n'v' - показать коды приглашений\
+        \n'k' - отключить участника\
+        \n'b' - заблокировать участника или адрес\
+        \n'u' - снять блокировку\
+        \n'n' - показать блокировки\
+        \n'p' - разослать лидерборд участникам\
+        \n'r' - показать аналитику попыток\
+        \n'e' - выгрузить результаты в CSV и JSON\
+        \n'x' - завершить работу сервера",
+        "Welcome!\
+        \n's' - start the experiment\
+        \n'd' - schedule a countdown start\
+        \n'c' - cancel the scheduled start\
+        \n'q' - show late participants waiting to be let in\
+        \n'j' - let late participants in\
+        \n'z' - restart participants\
+        \n'a' - answer a participant\
+        \n'l' - show the leaderboard\
+        \n'w' - show pending guesses\
+        \n'f' - show failed logins\
+        \n'i' - issue an invite code\
+        \n'v' - show invite codes\
+        \n'k' - kick a participant\
+        \n'b' - ban a participant or an address\
+        \n'u' - lift a ban\
+        \n'n' - show bans\
+        \n'p' - push the leaderboard to participants\
+        \n'r' - show guessing analytics\
+        \n'e' - export the results to CSV and JSON\
+        \n'x' - shut the server down",
+      ),
+      UuidPrompt => ("Уид: ", "UUID: "),
+      UuidsPrompt => ("Уиды через пробел: ", "UUIDs separated by spaces: "),
+      AnswerPrompt => ("Ответ (<, >, =): ", "Answer (<, >, =): "),
+      InvalidSymbol => ("Невалидный символ!", "Invalid symbol!"),
+      AdminSolvedHeader => (
+        "Угадали (место, уид, участник, попыток, время, стратегия):",
+        "Solved (rank, uuid, participant, guesses, time, strategy):",
+      ),
+      AdminUnsolvedHeader => (
+        "Не угадали (уид, участник, попыток, итог, стратегия):",
+        "Not solved (uuid, participant, guesses, outcome, strategy):",
+      ),
+      StrategyBinary => ("двоичный поиск", "binary search"),
+      StrategyLinear => ("перебор", "linear scan"),
+      StrategyRandom => ("наугад", "random"),
+      LeaderboardPublished => (
+        "Лидерборд разослан участникам: {}",
+        "Leaderboard pushed to participants: {}",
+      ),
+      AnalyticsHeader => ("\nАналитика:", "\nAnalytics:"),
+      AnalyticsSolved => ("Угадали: {} из {}", "Solved: {} of {}"),
+      AnalyticsAttempts => (
+        "Попыток до угадывания: среднее {}, медиана {}",
+        "Guesses to solve: mean {}, median {}",
+      ),
+      AnalyticsOptimal => (
+        "Двоичный поиск: не больше {} попыток, уложились {} из угадавших",
+        "Binary search: at most {} guesses, {} of the solvers kept within it",
+      ),
+      AnalyticsHistogram => (
+        "Распределение попыток (попыток: участников):",
+        "Guesses distribution (guesses: participants):",
+      ),
+      AnalyticsOutside => (
+        "Попытки вне допустимого интервала: {} из {}",
+        "Guesses outside the feasible interval: {} of {}",
+      ),
+      AnalyticsFirstGuesses => (
+        "Первые попытки (число: участников):",
+        "First guesses (number: participants):",
+      ),
+      LateQueueHeader => (
+        "\nЖдут допуска (уид, участник)",
+        "\nWaiting to be let in (uuid, participant)",
+      ),
+      WaitHeader => (
+        "\nОжидающие ответа (уид, участник, предположение)",
+        "\nAwaiting answer (uuid, participant, guess)",
+      ),
+      AuthSucceeded => (
+        "Аутентификация успешна! Роль: {}",
+        "Authentication succeeded! Role: {}",
+      ),
+      AuthFailed => ("Аутентификация неуспешна!", "Authentication failed!"),
+      AuthLocked => (
+        "Слишком много неудачных попыток, повторите через {} с",
+        "Too many failed attempts, retry in {} s",
+      ),
+      AuthFailuresHeader => (
+        "\nНеудачные попытки аутентификации (время, адрес, админ, причина):",
+        "\nFailed authentications (time, address, admin, reason):",
+      ),
+      AuthFailureLine => ("{} :: {} :: {} :: {}", "{} :: {} :: {} :: {}"),
+      PermissionDenied => (
+        "\nНедостаточно прав: нужна роль {}",
+        "\nPermission denied: role {} is required",
+      ),
+      InviteLabelPrompt => (
+        "Имя участника или группы: ",
+        "Participant or cohort name: ",
+      ),
+      InviteUsesPrompt => (
+        "Сколько регистраций (по умолчанию 1): ",
+        "Number of registrations (1 by default): ",
+      ),
+      InviteCreated => (
+        "\nКод приглашения: {} (метка {}, регистраций {})",
+        "\nInvite code: {} (label {}, uses {})",
+      ),
+      BanTargetPrompt => ("Уид или IP: ", "UUID or IP: "),
+      BanTargetInvalid => (
+        "Не уид и не IP-адрес: '{}'",
+        "Neither a UUID nor an IP address: '{}'",
+      ),
+      ExportDirPrompt => (
+        "Папка для выгрузки (по умолчанию текущая): ",
+        "Export directory (current by default): ",
+      ),
+      ExportSaved => ("Результаты выгружены: {}", "Results exported: {}"),
+      ExportFailed => (
+        "Не удалось выгрузить результаты: {}",
+        "Failed to export the results: {}",
+      ),
+      ShutdownConfirm => (
+        "Завершить работу сервера? (y/n): ",
+        "Shut the server down? (y/n): ",
+      ),
+      StartAtPrompt => (
+        "Через сколько секунд или во сколько (ЧЧ:ММ) начать: ",
+        "Start in how many seconds or at what time (HH:MM): ",
+      ),
+      ReadyCheckConfirm => (
+        "Сначала проверить готовность участников? (y/n): ",
+        "Check participant readiness first? (y/n): ",
+      ),
+      LobbyStatus => ("Готовы к старту: {} из {}", "Ready to start: {} of {}"),
+      StartScheduledAt => ("Отсчёт пошёл, старт в {}", "Countdown running, start at {}"),
+      LateJoinStarted => (
+        "Опоздавший участник {} ({}) начал эксперимент",
+        "Late participant {} ({}) has started the experiment",
+      ),
+      LateJoinQueued => (
+        "Опоздавший участник {} ({}) ждёт допуска, 'j' - допустить",
+        "Late participant {} ({}) is waiting to be let in, 'j' - let in",
+      ),
+      LateAdmitted => ("Допущено опоздавших: {}", "Late participants let in: {}"),
+      InvalidStartTime => (
+        "Сервер не может назначить старт на это время",
+        "The server cannot schedule a start at that time",
+      ),
+      StartDelayTooLong => (
+        "Старт можно назначить не позже чем через {} с",
+        "A start can be scheduled at most {} s ahead",
+      ),
+      StartTimeSkipped => (
+        "Времени {} нет в местном часовом поясе",
+        "{} does not exist in the local time zone",
+      ),
+      AlreadyStarted => (
+        "Эксперимент уже идёт, назначить старт нельзя",
+        "The experiment is already running, a start cannot be scheduled",
+      ),
+      RestartedCount => (
+        "Прогресс сброшен участникам: {}",
+        "Progress reset for participants: {}",
+      ),
+      ReasonPrompt => ("Причина: ", "Reason: "),
+      KickedCount => ("\nОтключено участников: {}", "\nParticipants disconnected: {}"),
+      Unbanned => ("\nБлокировка снята", "\nBan removed"),
+      UnbanMissing => ("\nТакой блокировки нет", "\nNo such ban"),
+      BansHeader => (
+        "\nБлокировки (цель, причина, админ, время):",
+        "\nBans (target, reason, admin, time):",
+      ),
+      InvitesHeader => (
+        "\nКоды приглашений (код, метка, осталось регистраций):",
+        "\nInvite codes (code, label, uses left):",
+      ),
+
+      ServerAuthToken => ("Токен аутентификации: {}", "Authentication token: {}"),
+      InvitesLoadFailed => (
+        "Не удалось загрузить коды приглашений: {}",
+        "Failed to load invite codes: {}",
+      ),
+      InviteEmpty => (
+        "пустой код или ноль регистраций: '{}'",
+        "empty code or zero uses: '{}'",
+      ),
+      InviteDuplicate => (
+        "код приглашения повторяется: '{}'",
+        "duplicate invite code: '{}'",
+      ),
+      ConfigLoadFailed => (
+        "Не удалось загрузить файл настроек: {}",
+        "Failed to load the configuration file: {}",
+      ),
+      ConfigInvalid => ("Недопустимые настройки: {}", "Invalid configuration: {}"),
+      ConfigTokenWithAccounts => ("auth.token и auth.accounts нельзя задать вместе", "auth.token and auth.accounts are mutually exclusive"),
+      ConfigTokenEmpty => ("auth.token пуст", "auth.token is empty"),
+      ConfigTlsPair => ("tls.cert и tls.key задаются только вместе", "tls.cert and tls.key must be given together"),
+      ConfigLockoutRange => ("auth.lockout_secs больше auth.lockout_max_secs", "auth.lockout_secs exceeds auth.lockout_max_secs"),
+      ConfigPayloadZero => ("limits.max_payload_bytes должен быть больше нуля", "limits.max_payload_bytes must be positive"),
+      ConfigValuesWithRange => ("experiment.values нельзя задать вместе с experiment.low, high и step", "experiment.values excludes experiment.low, high and step"),
+      ConfigStepZero => ("experiment.step должен быть больше нуля", "experiment.step must be positive"),
+      ConfigDomainEmpty => ("в experiment не остаётся допустимых чисел", "experiment allows no values"),
+      ConfigLimitZero => ("ограничения в experiment должны быть больше нуля", "experiment limits must be positive"),
+      ConfigIdleWithoutHeartbeat => ("limits.idle_timeout_secs требует limits.heartbeat_secs", "limits.idle_timeout_secs requires limits.heartbeat_secs"),
+      ConfigIdleTooShort => ("limits.idle_timeout_secs должен быть больше limits.heartbeat_secs", "limits.idle_timeout_secs must exceed limits.heartbeat_secs"),
+      AccountsLoadFailed => (
+        "Не удалось загрузить учётные записи админов: {}",
+        "Failed to load admin accounts: {}",
+      ),
+      AccountEmpty => (
+        "пустое имя или токен: '{}'",
+        "empty name or token: '{}'",
+      ),
+      AccountDuplicate => (
+        "имя админа повторяется: '{}'",
+        "duplicate admin name: '{}'",
+      ),
+      LogFileFailed => (
+        "Не удалось открыть файл журнала: {}",
+        "Failed to open log file: {}",
+      ),
+      Listening => ("Слушаем на {} & id({})", "Listening on {} & id({})"),
+      TlsListening => ("TLS слушаем на {}", "TLS listening on {}"),
+      TlsConfigFailed => (
+        "Не удалось загрузить сертификат или ключ TLS: {}",
+        "Failed to load TLS certificate or key: {}",
+      ),
+      MetricsListening => (
+        "Метрики доступны на http://{}/metrics",
+        "Metrics are served at http://{}/metrics",
+      ),
+      MetricsFailed => (
+        "Не удалось открыть эндпоинт метрик: {}",
+        "Failed to open metrics endpoint: {}",
+      ),
+      ListenFailed => (
+        "Не удалось открыть эндпоинт: {}",
+        "Failed to open endpoint: {}",
+      ),
+      SignalHandlerFailed => (
+        "Не удалось перехватить SIGINT/SIGTERM: {}",
+        "Failed to install the SIGINT/SIGTERM handler: {}",
+      ),
+      ShuttingDown => (
+        "Завершение работы: инициатор({})",
+        "Shutting down: initiator({})",
+      ),
+      FinalStats => (
+        "Итоги: участников({}) & попыток({}) & ожидают ответа({})",
+        "Final stats: participants({}) & guesses({}) & awaiting answer({})",
+      ),
+      ReportWritten => (
+        "Итоговый отчёт записан: файл({})",
+        "Final report written: file({})",
+      ),
+      ReportFailed => (
+        "Не удалось записать итоговый отчёт: {}",
+        "Failed to write the final report: {}",
+      ),
+      PublishingLeaderboard => (
+        "Рассылка лидерборда: инициатор({}) & участников({})",
+        "Pushing the leaderboard: initiator({}) & participants({})",
+      ),
+      ResultsExported => (
+        "Результаты выгружены: папка({})",
+        "Results exported: directory({})",
+      ),
+      ResultsExportFailed => (
+        "Не удалось выгрузить результаты: {}",
+        "Failed to export the results: {}",
+      ),
+      SendingExport => (
+        "Отправка результатов: админ({})",
+        "Sending results: admin({})",
+      ),
+      SendingAnalytics => (
+        "Отправка аналитики: админ({})",
+        "Sending analytics: admin({})",
+      ),
+      EndpointConnected => (
+        "Клиент подключился: эндпоинт({})",
+        "Client connected: endpoint({})",
+      ),
+      EndpointDisconnected => (
+        "Клиент отключился: эндпоинт({})",
+        "Client disconnected: endpoint({})",
+      ),
+      EndpointInvalidCategory => (
+        "Невалидная категория сообщения: эндпоинт({})",
+        "Invalid message category: endpoint({})",
+      ),
+      AdminChallenged => (
+        "Выдан вызов для аутентификации: эндпоинт({}) & админ({})",
+        "Authentication challenge issued: endpoint({}) & admin({})",
+      ),
+      AdminAuthed => (
+        "Аутентифицирован: эндпоинт({}) & админ({}) & роль({})",
+        "Authenticated: endpoint({}) & admin({}) & role({})",
+      ),
+      AdminAuthFailed => (
+        "Неудачная аутентификация: эндпоинт({}) & админ({}) & причина({})",
+        "Authentication failed: endpoint({}) & admin({}) & reason({})",
+      ),
+      AuthLockout => (
+        "Адрес заблокирован: адрес({}) & секунд({})",
+        "Address locked out: address({}) & seconds({})",
+      ),
+      EndpointDropped => (
+        "Соединение закрыто сервером: эндпоинт({})",
+        "Connection closed by server: endpoint({})",
+      ),
+      EndpointIdle => (
+        "Соединение молчит дольше таймаута и закрывается: эндпоинт({})",
+        "Connection idle past the timeout, closing: endpoint({})",
+      ),
+      FloodDetected => (
+        "Превышено ограничение, соединение закрывается: эндпоинт({}) & ограничение({})",
+        "Limit exceeded, closing connection: endpoint({}) & limit({})",
+      ),
+      SendingAuthFailures => (
+        "Отправка журнала неудачных входов: админ({})",
+        "Sending failed authentication log: admin({})",
+      ),
+      AdminForbidden => (
+        "Недостаточно прав: админ({}) & роль({}) & команда({})",
+        "Permission denied: admin({}) & role({}) & command({})",
+      ),
+      AdminUnauthed => (
+        "Доступ к админке без аутентификации: эндпоинт({})",
+        "Admin access without authentication: endpoint({})",
+      ),
+      StartBroadcastBegin => (
+        "Рассылка начала игры начата: админ({})",
+        "Broadcasting experiment start: admin({})",
+      ),
+      StartBroadcastEnd => (
+        "Рассылка начала игры закончена",
+        "Experiment start broadcast finished",
+      ),
+      StartScheduling => (
+        "Назначен старт: админ({}) & когда({}) & проверка готовности({})",
+        "Start scheduled: admin({}) & at({}) & ready check({})",
+      ),
+      ScheduleCancelled => ("Старт отменён: админ({})", "Start cancelled: admin({})"),
+      CountdownStarted => ("Отсчёт до старта: старт({})", "Countdown started: start({})"),
+      ParticipantReady => ("Участник готов: уид({})", "Participant ready: uuid({})"),
+      LateJoiner => (
+        "Участник подключился после старта: уид({}) & в очереди({})",
+        "Participant joined after the start: uuid({}) & queued({})",
+      ),
+      AdmittingLate => (
+        "Допуск опоздавших: админ({}) & участников({})",
+        "Admitting late participants: admin({}) & participants({})",
+      ),
+      ParticipantRestarted => (
+        "Прогресс участника сброшен: админ({}) & уид({})",
+        "Participant progress reset: admin({}) & uuid({})",
+      ),
+      Sending => (
+        "Отправка: эндпоинт({}) & сообщение({})",
+        "Sending: endpoint({}) & message({})",
+      ),
+      SendingStats => (
+        "Отправка статистики: админ({})",
+        "Sending statistics: admin({})",
+      ),
+      SendingLateQueue => (
+        "Отправка очереди опоздавших: админ({})",
+        "Sending late joiner queue: admin({})",
+      ),
+      SendingWaitList => (
+        "Отправка списка ожидания: админ({})",
+        "Sending pending list: admin({})",
+      ),
+      AnswerReceived => (
+        "Принят ответ на попытку: админ({}) & ответ({}) @ таргет({})",
+        "Answer received: admin({}) & answer({}) @ target({})",
+      ),
+      ParticipantNotFound => ("Клиент не найден!", "Participant not found!"),
+      UserRegistered => (
+        "Зарегистрирован юзер: эндпоинт({}) & уид({}) & имя({}) & приглашение({})",
+        "User registered: endpoint({}) & uuid({}) & name({}) & invite({})",
+      ),
+      UserRejected => (
+        "Регистрация отклонена: эндпоинт({}) & причина({}) & подробности({})",
+        "Registration rejected: endpoint({}) & reason({}) & detail({})",
+      ),
+      ProfileName => ("имя", "name"),
+      ProfileGroup => ("группа", "group"),
+      ProfileStudentId => ("номер студенческого", "student id"),
+      ProfileFieldEmpty => ("{}: пусто", "{} is empty"),
+      ProfileFieldTooLong => (
+        "{}: длиннее {} символов",
+        "{} is longer than {} characters",
+      ),
+      ProfileFieldControl => (
+        "{}: есть управляющие символы",
+        "{} contains control characters",
+      ),
+      InviteIssued => (
+        "Выдан код приглашения: админ({}) & метка({}) & регистраций({})",
+        "Invite code issued: admin({}) & label({}) & uses({})",
+      ),
+      UserKicked => (
+        "Участник отключён: эндпоинт({}) & причина({}) & заблокирован({})",
+        "Participant kicked: endpoint({}) & reason({}) & banned({})",
+      ),
+      KickRequested => (
+        "Отключение участника: админ({}) & уид({}) & причина({})",
+        "Kicking participant: admin({}) & uuid({}) & reason({})",
+      ),
+      BanAdded => (
+        "Блокировка: админ({}) & цель({}) & причина({})",
+        "Ban: admin({}) & target({}) & reason({})",
+      ),
+      BanRemoved => (
+        "Снятие блокировки: админ({}) & цель({}) & снята({})",
+        "Unban: admin({}) & target({}) & removed({})",
+      ),
+      SendingBans => (
+        "Отправка списка блокировок: админ({})",
+        "Sending ban list: admin({})",
+      ),
+      SendingInvites => (
+        "Отправка кодов приглашений: админ({})",
+        "Sending invite codes: admin({})",
+      ),
+      UserTakenOver => (
+        "Уид занят старым соединением, оно закрыто: эндпоинт({}) & уид({}) & старый эндпоинт({})",
+        "UUID held by a stale connection, closing it: endpoint({}) & uuid({}) & old endpoint({})",
+      ),
+      UserResumed => (
+        "Переподключен юзер: эндпоинт({}) & уид({})",
+        "User resumed: endpoint({}) & uuid({})",
+      ),
+      GuessReceived => (
+        "Попытка: эндпоинт({}) & попытка({})",
+        "Guess: endpoint({}) & guess({})",
+      ),
+      GuessBeforeStart => (
+        "Попытка до старта: эндпоинт({}) & попытка({})",
+        "Guess before the start: endpoint({}) & guess({})",
+      ),
+      GuessBeforeRestart => (
+        "Попытка до подтверждения сброса: эндпоинт({}) & попытка({})",
+        "Guess before the restart was acknowledged: endpoint({}) & guess({})",
+      ),
+      GuessOutOfDomain => (
+        "Попытка вне допустимых чисел: эндпоинт({}) & попытка({})",
+        "Guess outside the allowed numbers: endpoint({}) & guess({})",
+      ),
+      ParticipantFinished => (
+        "Участник выбыл: таргет({}) & итог({})",
+        "Participant is out: target({}) & outcome({})",
+      ),
+      UserNotFound => ("Не удалось найти юзера", "User not found"),
+      AckReceived => ("Получено подтверждение: уид({})", "Acknowledged: uuid({})"),
+    }
+  }
+}
@@ -0,0 +1,84 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{i18n::Msg, tr};
+
+/// Символы кода приглашения: без похожих друг на друга 0/O, 1/I/L
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const CODE_LEN: usize = 8;
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Invite {
+  pub code: String,
+  /// Имя участника или группы, для которой выдан код
+  pub label: String,
+  /// Сколько ещё регистраций можно сделать по коду
+  pub uses: u32,
+}
+
+/// Коды приглашений. Файл с заранее выданными кодами:
+///
+/// ```toml
+/// [[invite]]
+/// code = "GROUP-A"
+/// label = "группа А"
+/// uses = 30
+/// ```
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub struct Invites {
+  #[serde(rename = "invite", default)]
+  pub invites: Vec<Invite>,
+}
+
+impl Invites {
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let data = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let invites: Self = toml::from_str(&data).map_err(|err| err.to_string())?;
+    invites.validate()?;
+    Ok(invites)
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    let mut codes = HashSet::new();
+    for invite in &self.invites {
+      if invite.code.is_empty() || invite.uses == 0 {
+        return Err(tr!(Msg::InviteEmpty, invite.code));
+      }
+      if !codes.insert(&invite.code) {
+        return Err(tr!(Msg::InviteDuplicate, invite.code));
+      }
+    }
+    Ok(())
+  }
+
+  /// Выдаёт новый случайный код на `uses` регистраций
+  pub fn create(&mut self, label: String, uses: u32) -> Invite {
+    let mut rng = rand::thread_rng();
+    let code = loop {
+      let code: String = (0..CODE_LEN)
+        .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+        .collect();
+      if !self.invites.iter().any(|i| i.code == code) {
+        break code;
+      }
+    };
+    let invite = Invite { code, label, uses };
+    self.invites.push(invite.clone());
+    invite
+  }
+
+  /// Тратит одно использование кода и возвращает его метку.
+  /// Исчерпанный код удаляется
+  pub fn redeem(&mut self, code: &str) -> Option<String> {
+    let pos = self.invites.iter().position(|i| i.code == code)?;
+    let invite = &mut self.invites[pos];
+    invite.uses -= 1;
+    let label = invite.label.clone();
+    if invite.uses == 0 {
+      self.invites.remove(pos);
+    }
+    Some(label)
+  }
+}
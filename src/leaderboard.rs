@@ -0,0 +1,259 @@
+use std::{cmp::Ordering, time::Duration};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+  i18n::{self, Msg},
+  strategy::Assessment,
+  tr, Outcome,
+};
+
+/// Правило, по которому упорядочиваются угадавшие
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Ranking {
+  #[value(help = i18n::text(Msg::CliRankingGuesses))]
+  Guesses,
+  #[value(help = i18n::text(Msg::CliRankingTime))]
+  Time,
+  #[value(help = i18n::text(Msg::CliRankingScore))]
+  Score,
+}
+
+/// Место участника в лидерборде
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Standing {
+  /// UUID служит токеном для `--resume`, поэтому участникам
+  /// рассылается только их собственный
+  pub uuid: Option<Uuid>,
+  pub name: String,
+  /// Попытки до угадывания включительно, у не угадавших - все
+  pub guesses: u64,
+  /// От начала эксперимента до ответа `Equal`
+  pub time_to_solve: Option<Duration>,
+  /// Место среди угадавших, с единицы
+  pub rank: Option<usize>,
+  /// Почему выбыл, если не угадал
+  pub outcome: Option<Outcome>,
+  /// Стратегия, если попыток хватает для оценки
+  pub assessment: Option<Assessment>,
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Leaderboard {
+  pub ranking: Ranking,
+  /// Штраф за попытку в правиле `Score`
+  pub seconds_per_guess: u64,
+  pub solved: Vec<Standing>,
+  /// Не угадавшие, по числу попыток
+  pub unsolved: Vec<Standing>,
+}
+
+impl Leaderboard {
+  /// Упорядочивает участников: `solved` отделяет угадавших от остальных
+  pub fn new(
+    ranking: Ranking,
+    seconds_per_guess: u64,
+    standings: impl Iterator<Item = (Standing, bool)>,
+  ) -> Self {
+    let (solved, unsolved): (Vec<_>, Vec<_>) = standings.partition(|(_, solved)| *solved);
+    let mut solved: Vec<Standing> = solved.into_iter().map(|(s, _)| s).collect();
+    let mut unsolved: Vec<Standing> = unsolved.into_iter().map(|(s, _)| s).collect();
+    // Без времени (эксперимент не начинали) угадавший оказывается позади
+    let time = |s: &Standing| s.time_to_solve.unwrap_or(Duration::MAX);
+    let score = |s: &Standing| {
+      let guesses = u32::try_from(s.guesses).unwrap_or(u32::MAX);
+      time(s).saturating_add(Duration::from_secs(seconds_per_guess).saturating_mul(guesses))
+    };
+    solved.sort_by(|a, b| {
+      let order = match ranking {
+        Ranking::Guesses => a.guesses.cmp(&b.guesses).then(time(a).cmp(&time(b))),
+        Ranking::Time => time(a).cmp(&time(b)).then(a.guesses.cmp(&b.guesses)),
+        Ranking::Score => score(a).cmp(&score(b)),
+      };
+      order.then_with(|| a.name.cmp(&b.name))
+    });
+    // Равные по правилу делят место
+    let mut rank = 0;
+    for i in 0..solved.len() {
+      let tied = i > 0
+        && match ranking {
+          Ranking::Guesses | Ranking::Time => {
+            solved[i].guesses == solved[i - 1].guesses && time(&solved[i]) == time(&solved[i - 1])
+          }
+          Ranking::Score => score(&solved[i]) == score(&solved[i - 1]),
+        };
+      if !tied {
+        rank = i + 1;
+      }
+      solved[i].rank = Some(rank);
+    }
+    unsolved.sort_by(|a, b| match a.guesses.cmp(&b.guesses) {
+      Ordering::Equal => a.name.cmp(&b.name),
+      order => order,
+    });
+    Self {
+      ranking,
+      seconds_per_guess,
+      solved,
+      unsolved,
+    }
+  }
+
+  /// Копия для участника `uuid`: чужие UUID и стратегии стёрты
+  pub fn for_participant(&self, uuid: Uuid) -> Self {
+    let mut copy = self.clone();
+    for standing in copy.solved.iter_mut().chain(copy.unsolved.iter_mut()) {
+      standing.uuid = standing.uuid.filter(|own| *own == uuid);
+      if standing.uuid.is_none() {
+        standing.assessment = None;
+      }
+    }
+    copy
+  }
+
+  /// Переведённое описание правила
+  pub fn describe(&self) -> String {
+    match self.ranking {
+      Ranking::Guesses => tr!(Msg::RankingGuesses),
+      Ranking::Time => tr!(Msg::RankingTime),
+      Ranking::Score => tr!(Msg::RankingScore, self.seconds_per_guess),
+    }
+  }
+}
+
+/// Переведённая причина выбывания, прочерк для тех, кто ещё угадывает
+pub fn outcome(outcome: Option<Outcome>) -> String {
+  match outcome {
+    Some(Outcome::OutOfAttempts) => tr!(Msg::OutcomeOutOfAttempts),
+    Some(Outcome::TimeUp) => tr!(Msg::OutcomeTimeUp),
+    None => "-".to_string(),
+  }
+}
+
+/// `м:сс` или `ч:мм:сс`, прочерк без времени
+pub fn clock(duration: Option<Duration>) -> String {
+  let Some(duration) = duration else {
+    return "-".to_string();
+  };
+  let secs = duration.as_secs();
+  match secs / 3600 {
+    0 => format!("{}:{:02}", secs / 60, secs % 60),
+    hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn standing(name: &str, guesses: u64, secs: Option<u64>) -> Standing {
+    Standing {
+      uuid: None,
+      name: name.to_string(),
+      guesses,
+      time_to_solve: secs.map(Duration::from_secs),
+      rank: None,
+      outcome: None,
+      assessment: None,
+    }
+  }
+
+  /// Имена и места угадавших по порядку
+  fn places(ranking: Ranking, seconds_per_guess: u64, solved: &[Standing]) -> Vec<(String, usize)> {
+    let standings = solved.iter().cloned().map(|standing| (standing, true));
+    Leaderboard::new(ranking, seconds_per_guess, standings)
+      .solved
+      .into_iter()
+      .map(|standing| (standing.name, standing.rank.unwrap()))
+      .collect()
+  }
+
+  fn expected(places: &[(&str, usize)]) -> Vec<(String, usize)> {
+    places
+      .iter()
+      .map(|&(name, rank)| (name.to_string(), rank))
+      .collect()
+  }
+
+  #[test]
+  fn guesses_then_time() {
+    let solved = [
+      standing("d", 5, Some(10)),
+      standing("c", 3, Some(50)),
+      standing("b", 3, Some(20)),
+      standing("a", 3, Some(20)),
+    ];
+    assert_eq!(
+      places(Ranking::Guesses, 0, &solved),
+      expected(&[("a", 1), ("b", 1), ("c", 3), ("d", 4)])
+    );
+  }
+
+  #[test]
+  fn time_then_guesses() {
+    let solved = [
+      standing("d", 5, Some(10)),
+      standing("c", 3, Some(50)),
+      standing("b", 3, Some(20)),
+      standing("a", 3, Some(20)),
+    ];
+    assert_eq!(
+      places(Ranking::Time, 0, &solved),
+      expected(&[("d", 1), ("a", 2), ("b", 2), ("c", 4)])
+    );
+  }
+
+  #[test]
+  fn score_ties_across_guesses_and_time() {
+    // 2 * 10 + 30 = 4 * 10 + 10 = 50 < 1 * 10 + 60
+    let solved = [
+      standing("c", 1, Some(60)),
+      standing("b", 4, Some(10)),
+      standing("a", 2, Some(30)),
+    ];
+    assert_eq!(
+      places(Ranking::Score, 10, &solved),
+      expected(&[("a", 1), ("b", 1), ("c", 3)])
+    );
+  }
+
+  #[test]
+  fn solved_without_time_is_behind() {
+    let solved = [standing("a", 1, None), standing("b", 9, Some(100))];
+    assert_eq!(
+      places(Ranking::Time, 10, &solved),
+      expected(&[("b", 1), ("a", 2)])
+    );
+    assert_eq!(
+      places(Ranking::Score, 10, &solved),
+      expected(&[("b", 1), ("a", 2)])
+    );
+    // Без времени проигрывает только равному по попыткам
+    let solved = [standing("a", 3, None), standing("b", 3, Some(100))];
+    assert_eq!(
+      places(Ranking::Guesses, 0, &solved),
+      expected(&[("b", 1), ("a", 2)])
+    );
+  }
+
+  #[test]
+  fn unsolved_by_guesses_without_rank() {
+    let standings = [
+      (standing("c", 2, None), false),
+      (standing("b", 7, None), false),
+      (standing("a", 2, None), false),
+      (standing("w", 4, Some(5)), true),
+    ];
+    let leaderboard = Leaderboard::new(Ranking::Guesses, 0, standings.into_iter());
+    assert_eq!(leaderboard.solved.len(), 1);
+    let unsolved: Vec<_> = leaderboard
+      .unsolved
+      .iter()
+      .map(|standing| (standing.name.as_str(), standing.rank))
+      .collect();
+    assert_eq!(unsolved, [("a", None), ("c", None), ("b", None)]);
+  }
+}
@@ -1,58 +1,290 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use accounts::Role;
+use analytics::Analytics;
+use bans::{Ban, BanTarget};
+use export::Export;
+use invites::Invite;
+use leaderboard::Leaderboard;
+use profile::Profile;
+use rules::Rules;
+
+pub mod accounts;
+pub mod analytics;
+pub mod auth;
+pub mod bans;
+pub mod config;
+pub mod domain;
+pub mod export;
+pub mod heartbeat;
+pub mod history;
+pub mod i18n;
+pub mod invites;
+pub mod leaderboard;
+pub mod link;
+pub mod lockout;
+pub mod logging;
+pub mod metrics;
+pub mod profile;
+pub mod ratelimit;
+pub mod rules;
+pub mod strategy;
+pub mod tls;
+
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub enum ClientToServer {
-  Register,
+  /// Регистрация нового участника, при `--require-invite` только с кодом приглашения
+  Register {
+    invite: Option<String>,
+    profile: Profile,
+  },
+  /// Повторное подключение с ранее выданным UUID.
+  /// Анкета нужна, только если сервер этот UUID не помнит
+  Resume {
+    uuid: Uuid,
+    profile: Profile,
+  },
   Guess(i64),
   Ack(Uuid),
+  /// Ответ на `ServerToClient::ReadyCheck`
+  Ready,
 }
 
+/// Ответ на попытку: `Less` - загаданное число меньше предположения, `More` - больше
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
 pub enum GuessResult {
   Equal,
   Less,
   More,
 }
 
+/// Почему сервер отказал в регистрации
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+  /// Код приглашения не указан, неизвестен или исчерпан
+  Invite,
+  /// Анкета не прошла проверку
+  Profile,
+}
+
+/// Почему участник выбыл, не угадав
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+  OutOfAttempts,
+  TimeUp,
+}
+
+/// Когда начать эксперимент
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StartAt {
+  /// Через столько секунд, а с проверкой готовности - после того, как все готовы
+  After(u64),
+  At(DateTime<Utc>),
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub enum ServerToClient {
   RegisterUUID(Uuid),
-  ExperimentStart(Uuid),
+  RegisterRejected(RejectReason),
+  /// Админ проверяет готовность перед отсчётом, участник отвечает `Ready`
+  ReadyCheck,
+  /// Секунд до начала эксперимента
+  Countdown(u64),
+  /// Админ отменил назначенный старт
+  StartCancelled,
+  /// Начало эксперимента и его условия
+  ExperimentStart(Uuid, Rules),
+  /// Эксперимент уже идёт, участник ждёт, пока админ его допустит
+  LateQueued,
+  /// Эксперимент для участника ещё не начался, попытка не принята
+  NotStarted,
+  /// Админ сбросил прогресс участника и начал эксперимент для него заново
+  Restart(Uuid, Rules),
   Answer(GuessResult, Uuid),
+  /// Предположение вне допустимых чисел, сервер его не принял
+  GuessRejected(i64),
+  /// Попытки кончились, больше предположений сервер не примет
+  OutOfAttempts,
+  /// Время на эксперимент или на попытку вышло
+  TimeUp,
+  /// Админ отключил участника, после сообщения сервер закрывает соединение
+  Kicked {
+    reason: String,
+    banned: bool,
+  },
+  /// Лидерборд, разосланный админом
+  Leaderboard(Leaderboard),
+  /// Сервер завершает работу и вскоре закроет соединение
+  Shutdown,
 }
 
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub enum AdminToServer {
-  Auth(String),
+  /// Запрос вызова для аутентификации, сервер отвечает `ServerToAdmin::Challenge`
+  AuthRequest {
+    name: String,
+  },
+  /// Ответ на вызов, см. [`auth::sign`]
+  Auth(Vec<u8>),
   Start,
+  /// Назначить старт, по желанию после проверки готовности участников
+  ScheduleStart {
+    at: StartAt,
+    ready_check: bool,
+  },
+  /// Отменить назначенный старт
+  CancelStart,
+  /// Опоздавшие, ждущие допуска, сервер отвечает `ServerToAdmin::LateQueue`
+  LateQueue,
+  /// Начать эксперимент для опоздавших, ждущих допуска
+  AdmitLate,
+  /// Сбросить прогресс участников и начать для них эксперимент заново
+  Restart {
+    targets: Vec<Uuid>,
+  },
+  /// Лидерборд, сервер отвечает `ServerToAdmin::Leaderboard`
   Stats,
   WaitAnswers,
-  SendAnswer { target: Uuid, answer: GuessResult },
+  SendAnswer {
+    target: Uuid,
+    answer: GuessResult,
+  },
+  /// Журнал неудачных попыток аутентификации
+  AuthFailures,
+  /// Выдать код приглашения на `uses` регистраций
+  CreateInvite {
+    label: String,
+    uses: u32,
+  },
+  /// Список действующих кодов приглашений
+  Invites,
+  /// Отключить участника
+  Kick {
+    target: Uuid,
+    reason: String,
+  },
+  /// Отключить и больше не пускать участника или все подключения с адреса
+  Ban {
+    target: BanTarget,
+    reason: String,
+  },
+  Unban(BanTarget),
+  Bans,
+  /// Результаты эксперимента по участникам и попыткам
+  Export,
+  /// Сводка по тому, как участники угадывают
+  Analytics,
+  /// Разослать лидерборд подключённым участникам
+  PublishLeaderboard,
+  /// Завершить работу сервера
+  Shutdown,
 }
 
-/// Статистика по каждому участнику эксперимента
-/// Отображает UUID -> количество попыток угадать число
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
-pub struct Stats(pub HashMap<Uuid, u64>);
+impl AdminToServer {
+  /// Минимальная роль, с которой админ может выполнить команду
+  pub fn required_role(&self) -> Role {
+    match self {
+      AdminToServer::AuthRequest { .. } | AdminToServer::Auth(_) => Role::Observer,
+      AdminToServer::Stats
+      | AdminToServer::WaitAnswers
+      | AdminToServer::AuthFailures
+      | AdminToServer::Export
+      | AdminToServer::Analytics
+      | AdminToServer::LateQueue => Role::Observer,
+      AdminToServer::SendAnswer { .. } => Role::Operator,
+      AdminToServer::Start
+      | AdminToServer::ScheduleStart { .. }
+      | AdminToServer::CancelStart
+      | AdminToServer::AdmitLate
+      | AdminToServer::Restart { .. }
+      | AdminToServer::CreateInvite { .. }
+      | AdminToServer::Invites
+      | AdminToServer::Kick { .. }
+      | AdminToServer::Ban { .. }
+      | AdminToServer::Unban(_)
+      | AdminToServer::Bans
+      | AdminToServer::PublishLeaderboard
+      | AdminToServer::Shutdown => Role::Owner,
+    }
+  }
+}
 
 /// Множество ожидающих ответа
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct WaitAnswers(pub HashMap<Uuid, i64>);
 
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+/// Анкеты участников, упомянутых в ответе админу
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub struct Profiles(pub HashMap<Uuid, Profile>);
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub enum ServerToAdmin {
-  Stats(Stats),
-  WaitAnswers(WaitAnswers),
-  ResultAuth(bool),
+  Leaderboard(Leaderboard),
+  WaitAnswers(WaitAnswers, Profiles),
+  Challenge(auth::Nonce),
+  /// Роль админа при успешной аутентификации
+  ResultAuth(Option<Role>),
+  /// Условия эксперимента, сообщаются сразу после аутентификации
+  Experiment(Rules),
+  /// Сколько подключённых участников подтвердили готовность
+  Lobby {
+    ready: usize,
+    total: usize,
+  },
+  /// Начался отсчёт, эксперимент начнётся в указанный момент
+  StartScheduled(DateTime<Utc>),
+  StartCancelled,
+  /// Участник подключился после старта, `queued` - ждёт допуска
+  LateJoin {
+    uuid: Uuid,
+    name: String,
+    queued: bool,
+  },
+  /// Опоздавшие, ждущие допуска, в порядке подключения
+  LateQueue(Vec<Uuid>, Profiles),
+  /// Скольких опоздавших допустили
+  Admitted(usize),
+  /// Эксперимент уже идёт, назначить старт нельзя
+  AlreadyStarted,
+  /// Момент старта не выразить во времени сервера
+  InvalidStartTime,
+  /// Скольким участникам сброшен прогресс
+  Restarted(usize),
+  /// Для команды нужна указанная роль
+  PermissionDenied(Role),
+  /// Адрес заблокирован из-за неудачных попыток, секунд до разблокировки
+  AuthLocked(u64),
+  AuthFailures(Vec<auth::Failure>),
+  InviteCreated(Invite),
+  Invites(Vec<Invite>),
+  /// Сколько участников отключено командой
+  Kicked(usize),
+  /// Снята ли блокировка: `false`, если её не было
+  Unbanned(bool),
+  Bans(Vec<Ban>),
+  Export(Export),
+  Analytics(Analytics),
+  /// Скольким участникам разослан лидерборд
+  LeaderboardPublished(usize),
+  /// Сервер завершает работу и вскоре закроет соединение
+  Shutdown,
 }
 
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub enum Message {
   Cts(ClientToServer),
   Stc(ServerToClient),
   Ats(AdminToServer),
   Sta(ServerToAdmin),
+  /// Проверка связи, собеседник отвечает `Pong`
+  Ping,
+  Pong,
 }
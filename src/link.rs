@@ -0,0 +1,166 @@
+use std::{
+  net::SocketAddr,
+  process::exit,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use message_io::{
+  network::{Endpoint, Transport},
+  node::NodeHandler,
+};
+use rustls::ClientConfig;
+
+use crate::{
+  heartbeat::{self, Signal},
+  tls, Message,
+};
+
+/// Соединение клиента или админки с сервером, восстанавливаемое после обрыва
+pub struct Link {
+  pub server_addr: SocketAddr,
+  /// Настройки TLS, если сервер за туннелем
+  tls: Option<Arc<ClientConfig>>,
+  pub conn: Mutex<Connection>,
+  /// Сколько сервер может молчать, ноль - не проверять
+  server_timeout: Duration,
+  reconnect_attempts: u32,
+}
+
+/// Текущее соединение с сервером
+pub struct Connection {
+  pub endpoint: Endpoint,
+  pub local_addr: SocketAddr,
+  /// Соединение установлено и ещё не потеряно
+  pub connected: bool,
+  /// Когда от сервера в последний раз что-то приходило
+  pub last_seen: Instant,
+  /// Неудачные попытки переподключения подряд
+  pub attempts: u32,
+}
+
+/// Программа, которая держит соединение и сообщает о переподключениях
+pub trait Peer {
+  fn link(&self) -> &Link;
+  /// Началась попытка переподключения `attempt` из `attempts`
+  fn reconnecting(&self, attempt: u32, attempts: u32);
+  /// Попытки кончились, после этого программа завершается
+  fn disconnected(&self);
+}
+
+impl Link {
+  /// Подключается к серверу, с TLS - через локальный конец туннеля
+  pub fn open(
+    server_addr: SocketAddr,
+    tls: Option<Arc<ClientConfig>>,
+    server_timeout: Duration,
+    reconnect_attempts: u32,
+    handler: &NodeHandler<Signal>,
+  ) -> Result<Self, String> {
+    let (endpoint, local_addr) = connect(server_addr, tls.as_ref(), handler)?;
+    Ok(Self {
+      server_addr,
+      tls,
+      conn: Mutex::new(Connection {
+        endpoint,
+        local_addr,
+        connected: false,
+        last_seen: Instant::now(),
+        attempts: 0,
+      }),
+      server_timeout,
+      reconnect_attempts,
+    })
+  }
+
+  pub fn send(&self, handler: &NodeHandler<Signal>, message: &Message) {
+    let endpoint = self.conn.lock().unwrap().endpoint;
+    handler
+      .network()
+      .send(endpoint, &bincode::serialize(message).unwrap());
+  }
+
+  /// Запускает проверки сервера, если они не отключены
+  pub fn start_checks(&self, handler: &NodeHandler<Signal>) {
+    if !self.server_timeout.is_zero() {
+      handler
+        .signals()
+        .send_with_timer(Signal::Heartbeat, heartbeat::CHECK_INTERVAL);
+    }
+  }
+}
+
+fn connect(
+  server_addr: SocketAddr,
+  tls: Option<&Arc<ClientConfig>>,
+  handler: &NodeHandler<Signal>,
+) -> Result<(Endpoint, SocketAddr), String> {
+  let (connect_addr, tls_local_addr) = match tls {
+    Some(config) => {
+      let (inner, local_addr) =
+        tls::connect(config.clone(), server_addr).map_err(|err| err.to_string())?;
+      (inner, Some(local_addr))
+    }
+    None => (server_addr, None),
+  };
+  let (endpoint, local_addr) = handler
+    .network()
+    .connect(Transport::FramedTcp, connect_addr)
+    .map_err(|err| err.to_string())?;
+  Ok((endpoint, tls_local_addr.unwrap_or(local_addr)))
+}
+
+/// Бросает молчащее или оборванное соединение и начинает переподключение
+pub fn connection_lost(peer: &impl Peer, handler: &NodeHandler<Signal>) {
+  let mut conn = peer.link().conn.lock().unwrap();
+  if !conn.connected {
+    return;
+  }
+  conn.connected = false;
+  handler.network().remove(conn.endpoint.resource_id());
+  drop(conn);
+  reconnect(peer, handler);
+}
+
+pub fn reconnect(peer: &impl Peer, handler: &NodeHandler<Signal>) {
+  let link = peer.link();
+  let mut conn = link.conn.lock().unwrap();
+  if conn.attempts >= link.reconnect_attempts {
+    peer.disconnected();
+    handler.stop();
+    exit(-2);
+  }
+  conn.attempts += 1;
+  peer.reconnecting(conn.attempts, link.reconnect_attempts);
+  match connect(link.server_addr, link.tls.as_ref(), handler) {
+    Ok((endpoint, local_addr)) => {
+      conn.endpoint = endpoint;
+      conn.local_addr = local_addr;
+    }
+    Err(_) => {
+      handler
+        .signals()
+        .send_with_timer(Signal::Reconnect, heartbeat::RECONNECT_DELAY);
+    }
+  }
+}
+
+/// Пингует сервер, если тот притих, и переподключается, если он молчит слишком долго
+pub fn check_server(peer: &impl Peer, handler: &NodeHandler<Signal>) {
+  let link = peer.link();
+  let silence = {
+    let conn = link.conn.lock().unwrap();
+    match conn.connected {
+      true => conn.last_seen.elapsed(),
+      false => Duration::ZERO,
+    }
+  };
+  if silence > link.server_timeout {
+    connection_lost(peer, handler);
+  } else if silence > link.server_timeout / 3 {
+    link.send(handler, &Message::Ping);
+  }
+  handler
+    .signals()
+    .send_with_timer(Signal::Heartbeat, heartbeat::CHECK_INTERVAL);
+}
@@ -0,0 +1,110 @@
+use std::{
+  collections::HashMap,
+  hash::Hash,
+  time::{Duration, Instant},
+};
+
+/// Учёт неудачных попыток по ключу с экспоненциально растущей блокировкой:
+/// после n-й неудачи подряд ключ заблокирован на `base * 2^(n-1)`, но не дольше `max`
+pub struct Lockout<K> {
+  base: Duration,
+  max: Duration,
+  entries: HashMap<K, Entry>,
+}
+
+struct Entry {
+  failures: u32,
+  locked_until: Instant,
+}
+
+impl<K: Hash + Eq> Lockout<K> {
+  pub fn new(base: Duration, max: Duration) -> Self {
+    Self {
+      base,
+      max,
+      entries: HashMap::new(),
+    }
+  }
+
+  /// Сколько ещё продлится блокировка ключа
+  pub fn locked_for(&self, key: &K, now: Instant) -> Option<Duration> {
+    let entry = self.entries.get(key)?;
+    (entry.locked_until > now).then(|| entry.locked_until - now)
+  }
+
+  /// Учитывает неудачу и возвращает новую длительность блокировки
+  pub fn fail(&mut self, key: K, now: Instant) -> Duration {
+    let entry = self.entries.entry(key).or_insert(Entry {
+      failures: 0,
+      locked_until: now,
+    });
+    entry.failures = entry.failures.saturating_add(1);
+    let factor = 2u32.saturating_pow(entry.failures - 1);
+    let duration = self.base.saturating_mul(factor).min(self.max);
+    entry.locked_until = now + duration;
+    duration
+  }
+
+  pub fn reset(&mut self, key: &K) {
+    self.entries.remove(key);
+  }
+
+  /// Забывает ключи, блокировка которых давно истекла
+  pub fn prune(&mut self, now: Instant) {
+    let max = self.max;
+    self
+      .entries
+      .retain(|_, entry| entry.locked_until + max > now);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const BASE: Duration = Duration::from_secs(1);
+  const MAX: Duration = Duration::from_secs(10);
+
+  #[test]
+  fn doubles_up_to_max() {
+    let now = Instant::now();
+    let mut lockout = Lockout::new(BASE, MAX);
+    let durations: Vec<u64> = (0..6).map(|_| lockout.fail("ip", now).as_secs()).collect();
+    assert_eq!(durations, [1, 2, 4, 8, 10, 10]);
+    assert_eq!(lockout.locked_for(&"ip", now), Some(MAX));
+    assert_eq!(lockout.locked_for(&"other", now), None);
+  }
+
+  #[test]
+  fn many_failures_do_not_overflow() {
+    let now = Instant::now();
+    let mut lockout = Lockout::new(BASE, MAX);
+    for _ in 0..100 {
+      assert!(lockout.fail("ip", now) <= MAX);
+    }
+  }
+
+  #[test]
+  fn expires_and_resets() {
+    let now = Instant::now();
+    let mut lockout = Lockout::new(BASE, MAX);
+    lockout.fail("ip", now);
+    lockout.fail("ip", now);
+    assert_eq!(lockout.locked_for(&"ip", now + BASE), Some(BASE));
+    assert_eq!(lockout.locked_for(&"ip", now + BASE * 2), None);
+    // Счёт неудач продолжается и после истечения блокировки
+    assert_eq!(lockout.fail("ip", now + BASE * 2), BASE * 4);
+    lockout.reset(&"ip");
+    assert_eq!(lockout.locked_for(&"ip", now), None);
+    assert_eq!(lockout.fail("ip", now), BASE);
+  }
+
+  #[test]
+  fn prune_forgets_long_expired() {
+    let now = Instant::now();
+    let mut lockout = Lockout::new(BASE, MAX);
+    lockout.fail("ip", now);
+    lockout.prune(now + BASE + MAX);
+    assert_eq!(lockout.fail("ip", now + BASE + MAX), BASE);
+  }
+}
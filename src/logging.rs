@@ -0,0 +1,125 @@
+use std::{
+  fmt,
+  fs::OpenOptions,
+  io::{self, Write},
+  path::Path,
+  sync::{Mutex, OnceLock},
+};
+
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::i18n::{self, Msg};
+
+/// Уровень важности записи, от самых важных к самым подробным
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+  Error,
+  Warn,
+  Info,
+  Debug,
+}
+
+impl Level {
+  fn name(self) -> &'static str {
+    match self {
+      Level::Error => "error",
+      Level::Warn => "warn",
+      Level::Info => "info",
+      Level::Debug => "debug",
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+  #[value(help = i18n::text(Msg::CliLogFormatText))]
+  Text,
+  #[value(help = i18n::text(Msg::CliLogFormatJson))]
+  Json,
+}
+
+struct Logger {
+  level: Level,
+  format: Format,
+  out: Mutex<Box<dyn Write + Send>>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Настраивает журнал. Без файла записи пишутся в stdout
+pub fn init(level: Level, format: Format, file: Option<&Path>) -> io::Result<()> {
+  let out: Box<dyn Write + Send> = match file {
+    Some(path) => Box::new(OpenOptions::new().create(true).append(true).open(path)?),
+    None => Box::new(io::stdout()),
+  };
+  let _ = LOGGER.set(Logger {
+    level,
+    format,
+    out: Mutex::new(out),
+  });
+  Ok(())
+}
+
+/// До вызова `init` пишутся все записи в текстовом виде
+pub fn enabled(level: Level) -> bool {
+  LOGGER.get().is_none_or(|logger| level <= logger.level)
+}
+
+/// Пишет запись о событии `msg`. Значения полей подставляются в шаблон сообщения по порядку
+pub fn write(level: Level, msg: Msg, fields: &[(&str, &dyn fmt::Display)]) {
+  let values: Vec<&dyn fmt::Display> = fields.iter().map(|(_, v)| *v).collect();
+  let message = i18n::format(i18n::text(msg), &values);
+  let time = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+  let Some(logger) = LOGGER.get() else {
+    return println!("{} {:5} {}", time, level.name().to_uppercase(), message);
+  };
+  let line = match logger.format {
+    Format::Text => format!("{} {:5} {}", time, level.name().to_uppercase(), message),
+    Format::Json => {
+      let mut record = Map::new();
+      record.insert("time".into(), Value::String(time));
+      record.insert("level".into(), Value::String(level.name().into()));
+      record.insert("event".into(), Value::String(event_name(msg)));
+      record.insert("message".into(), Value::String(message));
+      for (key, value) in fields {
+        record.insert(key.to_string(), Value::String(value.to_string()));
+      }
+      Value::Object(record).to_string()
+    }
+  };
+  let mut out = logger.out.lock().unwrap();
+  let _ = writeln!(out, "{}", line);
+  let _ = out.flush();
+}
+
+/// Имя события для машинной обработки: `GuessReceived` -> `guess_received`
+fn event_name(msg: Msg) -> String {
+  let mut name = String::new();
+  for (i, c) in format!("{:?}", msg).chars().enumerate() {
+    if c.is_uppercase() && i != 0 {
+      name.push('_');
+    }
+    name.push(c.to_ascii_lowercase());
+  }
+  name
+}
+
+/// Пишет запись в журнал: `log_event!(Level::Info, Msg::UserRegistered, endpoint = e, uuid = u)`
+#[macro_export]
+macro_rules! log_event {
+  ($level:expr, $msg:expr $(, $key:ident = $value:expr)* $(,)?) => {
+    if $crate::logging::enabled($level) {
+      $crate::logging::write(
+        $level,
+        $msg,
+        &[$((stringify!($key), &$value as &dyn std::fmt::Display)),*],
+      );
+    }
+  };
+}
@@ -0,0 +1,182 @@
+use std::{
+  fmt::Write as _,
+  io::{self, BufRead, BufReader, Write},
+  net::{SocketAddr, TcpListener, TcpStream},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+  },
+  thread,
+  time::Duration,
+};
+
+/// Границы корзин гистограммы времени ответа, в секундах
+const LATENCY_BUCKETS: [f64; 10] = [0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0];
+
+/// Счётчик (только растёт) или показатель (выставляется целиком)
+#[derive(Default)]
+pub struct Value(AtomicU64);
+
+impl Value {
+  pub fn inc(&self) {
+    self.0.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn set(&self, value: usize) {
+    self.0.store(value as u64, Ordering::Relaxed);
+  }
+
+  pub fn get(&self) -> u64 {
+    self.0.load(Ordering::Relaxed)
+  }
+}
+
+/// Счётчики и показатели сервера. Обновляются из цикла событий, читаются HTTP-потоком
+#[derive(Default)]
+pub struct Metrics {
+  pub participants: Value,
+  pub admins: Value,
+  pub pending_answers: Value,
+  pub guesses_received: Value,
+  pub answers_sent: Value,
+  pub auth_failures: Value,
+  pub parse_errors: Value,
+  pub flood_disconnects: Value,
+  pub idle_disconnects: Value,
+  latency_buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+  latency_count: AtomicU64,
+  latency_sum_micros: AtomicU64,
+}
+
+impl Metrics {
+  /// Учитывает время между получением попытки и отправкой ответа на неё
+  pub fn observe_answer_latency(&self, latency: Duration) {
+    let secs = latency.as_secs_f64();
+    for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+      if secs <= *bound {
+        bucket.fetch_add(1, Ordering::Relaxed);
+      }
+    }
+    self.latency_count.fetch_add(1, Ordering::Relaxed);
+    self
+      .latency_sum_micros
+      .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+  }
+
+  /// Текстовый формат экспозиции Prometheus
+  pub fn render(&self) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: &Value| {
+      let _ = writeln!(out, "# HELP {} {}", name, help);
+      let _ = writeln!(out, "# TYPE {} {}", name, kind);
+      let _ = writeln!(out, "{} {}", name, value.get());
+    };
+    metric(
+      "hogwarts_participants_connected",
+      "gauge",
+      "Connected participants",
+      &self.participants,
+    );
+    metric(
+      "hogwarts_admins_connected",
+      "gauge",
+      "Authenticated admins",
+      &self.admins,
+    );
+    metric(
+      "hogwarts_pending_answers",
+      "gauge",
+      "Guesses waiting for an answer",
+      &self.pending_answers,
+    );
+    metric(
+      "hogwarts_guesses_received_total",
+      "counter",
+      "Guesses received from participants",
+      &self.guesses_received,
+    );
+    metric(
+      "hogwarts_answers_sent_total",
+      "counter",
+      "Answers sent to participants",
+      &self.answers_sent,
+    );
+    metric(
+      "hogwarts_auth_failures_total",
+      "counter",
+      "Failed admin authentications",
+      &self.auth_failures,
+    );
+    metric(
+      "hogwarts_parse_errors_total",
+      "counter",
+      "Messages that failed to deserialize",
+      &self.parse_errors,
+    );
+    metric(
+      "hogwarts_flood_disconnects_total",
+      "counter",
+      "Connections closed for exceeding size or rate limits",
+      &self.flood_disconnects,
+    );
+    metric(
+      "hogwarts_idle_disconnects_total",
+      "counter",
+      "Connections closed after the idle timeout",
+      &self.idle_disconnects,
+    );
+
+    let name = "hogwarts_answer_latency_seconds";
+    let _ = writeln!(
+      out,
+      "# HELP {} Time from receiving a guess to sending its answer",
+      name
+    );
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+      let _ = writeln!(
+        out,
+        "{}_bucket{{le=\"{}\"}} {}",
+        name,
+        bound,
+        bucket.load(Ordering::Relaxed)
+      );
+    }
+    let count = self.latency_count.load(Ordering::Relaxed);
+    let sum = self.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+    let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+    let _ = writeln!(out, "{}_sum {}", name, sum);
+    let _ = writeln!(out, "{}_count {}", name, count);
+    out
+  }
+
+  /// Запускает HTTP-сервер, отдающий метрики по `GET /metrics`
+  pub fn serve(self: Arc<Self>, address: SocketAddr) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(address)?;
+    let local_addr = listener.local_addr()?;
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        let _ = self.respond(stream);
+      }
+    });
+    Ok(local_addr)
+  }
+
+  fn respond(&self, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+      (Some("GET"), Some("/metrics")) => ("200 OK", self.render()),
+      _ => ("404 Not Found", String::new()),
+    };
+    write!(
+      stream,
+      "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+      status,
+      body.len(),
+      body
+    )
+  }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  i18n::{text, Msg},
+  tr,
+};
+
+/// Наибольшая длина любого поля анкеты, в символах
+pub const FIELD_MAX_LEN: usize = 64;
+
+/// Анкета участника, которую видят админы
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub struct Profile {
+  /// Отображаемое имя. Без него сервер подставит своё
+  pub name: Option<String>,
+  pub group: Option<String>,
+  /// Номер студенческого билета
+  pub student_id: Option<String>,
+}
+
+impl Profile {
+  /// Имя или пустая строка, если его не задали
+  pub fn name(&self) -> &str {
+    self.name.as_deref().unwrap_or_default()
+  }
+
+  /// Обрезает пробелы по краям полей, пустые группу и номер убирает.
+  /// Ошибка, если заданное имя пустое, а поле слишком длинное или
+  /// содержит управляющие символы
+  pub fn normalized(self) -> Result<Self, String> {
+    let field = |value: String, what: Msg| {
+      let value = value.trim().to_string();
+      if value.chars().count() > FIELD_MAX_LEN {
+        return Err(tr!(Msg::ProfileFieldTooLong, text(what), FIELD_MAX_LEN));
+      }
+      if value.chars().any(char::is_control) {
+        return Err(tr!(Msg::ProfileFieldControl, text(what)));
+      }
+      Ok(value)
+    };
+    let optional = |value: Option<String>, what: Msg| {
+      value
+        .map(|value| field(value, what))
+        .transpose()
+        .map(|value| value.filter(|value| !value.is_empty()))
+    };
+    let name = self
+      .name
+      .map(|name| field(name, Msg::ProfileName))
+      .transpose()?;
+    if name.as_deref() == Some("") {
+      return Err(tr!(Msg::ProfileFieldEmpty, text(Msg::ProfileName)));
+    }
+    Ok(Self {
+      name,
+      group: optional(self.group, Msg::ProfileGroup)?,
+      student_id: optional(self.student_id, Msg::ProfileStudentId)?,
+    })
+  }
+}
+
+/// `Имя (группа, номер)`
+impl fmt::Display for Profile {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.name())?;
+    let extra: Vec<&str> = [&self.group, &self.student_id]
+      .into_iter()
+      .flatten()
+      .map(String::as_str)
+      .collect();
+    if !extra.is_empty() {
+      write!(f, " ({})", extra.join(", "))?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,76 @@
+use std::time::Instant;
+
+/// Ведро токенов: в среднем `rate` событий в секунду, не больше `rate` подряд.
+/// Нулевая частота - без ограничения
+pub struct TokenBucket {
+  rate: f64,
+  tokens: f64,
+  last: Instant,
+}
+
+impl TokenBucket {
+  pub fn new(rate: u32, now: Instant) -> Self {
+    Self {
+      rate: rate as f64,
+      tokens: rate as f64,
+      last: now,
+    }
+  }
+
+  /// Тратит токен. `false`, если ведро пусто
+  pub fn take(&mut self, now: Instant) -> bool {
+    if self.rate == 0.0 {
+      return true;
+    }
+    let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+    self.tokens = (self.tokens + elapsed * self.rate).min(self.rate.max(1.0));
+    self.last = now;
+    if self.tokens < 1.0 {
+      return false;
+    }
+    self.tokens -= 1.0;
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::*;
+
+  #[test]
+  fn burst_then_refill() {
+    let now = Instant::now();
+    let mut bucket = TokenBucket::new(3, now);
+    assert!((0..3).all(|_| bucket.take(now)));
+    assert!(!bucket.take(now));
+    // Треть секунды - один токен
+    assert!(bucket.take(now + Duration::from_millis(340)));
+    assert!(!bucket.take(now + Duration::from_millis(340)));
+  }
+
+  #[test]
+  fn refill_is_capped() {
+    let now = Instant::now();
+    let mut bucket = TokenBucket::new(2, now);
+    let later = now + Duration::from_secs(60);
+    assert!((0..2).all(|_| bucket.take(later)));
+    assert!(!bucket.take(later));
+  }
+
+  #[test]
+  fn zero_rate_is_unlimited() {
+    let now = Instant::now();
+    let mut bucket = TokenBucket::new(0, now);
+    assert!((0..1000).all(|_| bucket.take(now)));
+  }
+
+  #[test]
+  fn clock_going_back_does_not_refill() {
+    let now = Instant::now() + Duration::from_secs(1);
+    let mut bucket = TokenBucket::new(1, now);
+    assert!(bucket.take(now));
+    assert!(!bucket.take(now - Duration::from_secs(1)));
+  }
+}
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{domain::Domain, i18n::Msg, leaderboard, tr};
+
+/// Условия эксперимента, о которых сервер сообщает участникам и админам
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub struct Rules {
+  pub domain: Domain,
+  /// Сколько попыток у участника
+  pub max_attempts: Option<u64>,
+  /// Сколько секунд у участника на весь эксперимент
+  pub time_limit_secs: Option<u64>,
+  /// Сколько секунд на попытку после ответа на предыдущую
+  pub guess_time_limit_secs: Option<u64>,
+}
+
+impl Rules {
+  /// Переведённое описание, по строке на условие
+  pub fn describe(&self) -> Vec<String> {
+    let mut lines = vec![tr!(Msg::DomainLine, self.domain.describe())];
+    if let Some(attempts) = self.max_attempts {
+      lines.push(tr!(Msg::RulesMaxAttempts, attempts));
+    }
+    if let Some(secs) = self.time_limit_secs {
+      let limit = leaderboard::clock(Some(Duration::from_secs(secs)));
+      lines.push(tr!(Msg::RulesTimeLimit, limit));
+    }
+    if let Some(secs) = self.guess_time_limit_secs {
+      lines.push(tr!(Msg::RulesGuessTimeLimit, secs));
+    }
+    lines
+  }
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use crate::history::{History, Interval};
+
+/// Меньше попыток не позволяет судить о стратегии
+pub const MIN_GUESSES: usize = 3;
+
+/// Как участник выбирает следующее число
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+  /// Делит допустимый интервал примерно пополам
+  Binary,
+  /// Перебирает числа с постоянным шагом
+  Linear,
+  /// Ни то, ни другое
+  Random,
+}
+
+/// Стратегия участника и её эффективность
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Assessment {
+  pub strategy: Strategy,
+  /// Насколько ровно попытки делят допустимый интервал, в процентах:
+  /// 100 - ровно пополам, 0 - с краю или вне интервала
+  pub efficiency: u8,
+}
+
+/// Оценивает стратегию по попыткам и ответам, `range` - интервал загаданного числа.
+/// `None`, если попыток меньше [`MIN_GUESSES`]
+pub fn classify(range: Interval, history: &History) -> Option<Assessment> {
+  if history.0.len() < MIN_GUESSES {
+    return None;
+  }
+  let mut interval = range;
+  let mut balances = Vec::with_capacity(history.0.len());
+  for entry in &history.0 {
+    balances.push(balance(interval, entry.guess));
+    if let Some(result) = entry.result {
+      interval.narrow(entry.guess, result);
+    }
+  }
+  let steps: Vec<i128> = history
+    .0
+    .windows(2)
+    .map(|pair| i128::from(pair[1].guess) - i128::from(pair[0].guess))
+    .collect();
+  // Самый частый шаг между соседними попытками
+  let common = steps
+    .iter()
+    .max_by_key(|step| steps.iter().filter(|other| other == step).count())
+    .copied()
+    .unwrap_or(0);
+  let strategy = if common != 0 && mostly(&steps, |step| *step == common) {
+    Strategy::Linear
+  } else if mostly(&balances, |balance| *balance >= 0.5) {
+    Strategy::Binary
+  } else {
+    Strategy::Random
+  };
+  let efficiency = balances.iter().sum::<f64>() / balances.len() as f64;
+  Some(Assessment {
+    strategy,
+    efficiency: (efficiency * 100.0).round() as u8,
+  })
+}
+
+/// Отношение меньшей части интервала к большей после попытки `guess`
+fn balance(interval: Interval, guess: i64) -> f64 {
+  if !interval.contains(guess) {
+    return 0.0;
+  }
+  let below = i128::from(guess) - i128::from(interval.low);
+  let above = i128::from(interval.high) - i128::from(guess);
+  match below.max(above) {
+    0 => 1.0,
+    larger => below.min(above) as f64 / larger as f64,
+  }
+}
+
+/// Условие выполняется хотя бы для трёх четвертей элементов
+fn mostly<T>(items: &[T], condition: impl Fn(&T) -> bool) -> bool {
+  items.iter().filter(|item| condition(item)).count() * 4 >= items.len() * 3
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    history::fixtures::{history, RANGE},
+    GuessResult,
+  };
+
+  #[test]
+  fn too_few_guesses() {
+    assert_eq!(classify(RANGE, &History::default()), None);
+    assert_eq!(classify(RANGE, &history(&[(50, None)])), None);
+    let two = history(&[(50, Some(GuessResult::More)), (75, None)]);
+    assert_eq!(classify(RANGE, &two), None);
+  }
+
+  #[test]
+  fn binary() {
+    let history = history(&[
+      (50, Some(GuessResult::More)),
+      (75, Some(GuessResult::Less)),
+      (62, Some(GuessResult::More)),
+      (68, Some(GuessResult::Equal)),
+    ]);
+    let assessment = classify(RANGE, &history).unwrap();
+    assert_eq!(assessment.strategy, Strategy::Binary);
+    assert!(assessment.efficiency >= 90);
+  }
+
+  #[test]
+  fn linear() {
+    let history = history(&[
+      (1, Some(GuessResult::More)),
+      (2, Some(GuessResult::More)),
+      (3, None),
+    ]);
+    let assessment = classify(RANGE, &history).unwrap();
+    assert_eq!(assessment.strategy, Strategy::Linear);
+    assert_eq!(assessment.efficiency, 0);
+  }
+
+  #[test]
+  fn mixed() {
+    // Первая попытка делит пополам, остальные - у краёв и с разным шагом
+    let history = history(&[
+      (50, Some(GuessResult::More)),
+      (52, Some(GuessResult::More)),
+      (99, Some(GuessResult::Less)),
+      (60, None),
+    ]);
+    assert_eq!(
+      classify(RANGE, &history).unwrap().strategy,
+      Strategy::Random
+    );
+  }
+
+  #[test]
+  fn outside_interval_counts_as_unbalanced() {
+    assert_eq!(balance(RANGE, 0), 0.0);
+    assert_eq!(balance(Interval { low: 5, high: 5 }, 5), 1.0);
+  }
+}
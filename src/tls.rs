@@ -0,0 +1,260 @@
+use std::{
+  collections::HashMap,
+  fs::File,
+  io::{self, BufReader, Read, Write},
+  net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+  path::Path,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+  thread,
+  time::Duration,
+};
+
+use rustls::{
+  pki_types::{CertificateDer, PrivateKeyDer, ServerName},
+  ClientConfig, ClientConnection, Connection, RootCertStore, ServerConfig, ServerConnection,
+};
+
+/// Сколько ждать подключения и завершения рукопожатия с другой стороны
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Адрес, с которого туннель подключён к внутреннему слушателю -> настоящий адрес клиента
+pub type PeerAddrs = Arc<Mutex<HashMap<SocketAddr, SocketAddr>>>;
+
+/// Настройки сервера из PEM-файлов с цепочкой сертификатов и закрытым ключом
+pub fn server_config(cert: &Path, key: &Path) -> Result<Arc<ServerConfig>, String> {
+  let certs = load_certs(cert)?;
+  let key = load_key(key)?;
+  let config = ServerConfig::builder()
+    .with_no_client_auth()
+    .with_single_cert(certs, key)
+    .map_err(|err| err.to_string())?;
+  Ok(Arc::new(config))
+}
+
+/// Настройки клиента. Без `ca_cert` сервер проверяется по общеизвестным корневым сертификатам
+pub fn client_config(ca_cert: Option<&Path>) -> Result<Arc<ClientConfig>, String> {
+  let mut roots = RootCertStore::empty();
+  match ca_cert {
+    Some(path) => {
+      for cert in load_certs(path)? {
+        roots.add(cert).map_err(|err| err.to_string())?;
+      }
+    }
+    None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+  }
+  let config = ClientConfig::builder()
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+  Ok(Arc::new(config))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+  let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+  let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|err| format!("{}: {}", path.display(), err))?;
+  if certs.is_empty() {
+    return Err(format!("{}: no certificates", path.display()));
+  }
+  Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+  let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+  rustls_pemfile::private_key(&mut BufReader::new(file))
+    .map_err(|err| format!("{}: {}", path.display(), err))?
+    .ok_or_else(|| format!("{}: no private key", path.display()))
+}
+
+/// Внешний слушатель TLS-соединений
+pub struct Listener {
+  pub local_addr: SocketAddr,
+  stopped: Arc<AtomicBool>,
+}
+
+impl Listener {
+  /// Перестаёт принимать соединения, уже открытые туннели продолжают работать
+  pub fn stop(&self) {
+    if self.stopped.swap(true, Ordering::SeqCst) {
+      return;
+    }
+    // Поток слушателя ждёт в accept, пустое подключение его будит
+    let mut wake = self.local_addr;
+    if wake.ip().is_unspecified() {
+      wake.set_ip(match wake {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+      });
+    }
+    let _ = TcpStream::connect_timeout(&wake, HANDSHAKE_TIMEOUT);
+  }
+}
+
+/// Принимает TLS-соединения на `address` и пробрасывает расшифрованные данные
+/// на внутренний слушатель `inner`, запоминая настоящие адреса клиентов в `peers`
+pub fn serve(
+  config: Arc<ServerConfig>,
+  address: SocketAddr,
+  inner: SocketAddr,
+  peers: PeerAddrs,
+) -> io::Result<Listener> {
+  let listener = TcpListener::bind(address)?;
+  let local_addr = listener.local_addr()?;
+  let stopped = Arc::new(AtomicBool::new(false));
+  let stop = stopped.clone();
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      if stop.load(Ordering::SeqCst) {
+        break;
+      }
+      let Ok(stream) = stream else {
+        continue;
+      };
+      let config = config.clone();
+      let peers = peers.clone();
+      let stop = stop.clone();
+      thread::spawn(move || {
+        let _ = accept(config, stream, inner, &peers, &stop);
+      });
+    }
+  });
+  Ok(Listener {
+    local_addr,
+    stopped,
+  })
+}
+
+fn accept(
+  config: Arc<ServerConfig>,
+  mut stream: TcpStream,
+  inner: SocketAddr,
+  peers: &PeerAddrs,
+  stopped: &AtomicBool,
+) -> io::Result<()> {
+  let peer = stream.peer_addr()?;
+  let mut conn = ServerConnection::new(config).map_err(io::Error::other)?;
+  stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+  while conn.is_handshaking() {
+    conn.complete_io(&mut stream)?;
+  }
+  stream.set_read_timeout(None)?;
+  // Рукопожатие закончилось, когда сервер уже завершает работу
+  if stopped.load(Ordering::SeqCst) {
+    return Ok(());
+  }
+
+  let plain = TcpStream::connect(inner)?;
+  let key = plain.local_addr()?;
+  peers.lock().unwrap().insert(key, peer);
+  tunnel(conn.into(), stream, plain);
+  peers.lock().unwrap().remove(&key);
+  Ok(())
+}
+
+/// Устанавливает TLS-соединение с сервером и открывает локальный слушатель,
+/// через который к серверу можно подключиться обычным TCP.
+/// Возвращает адрес слушателя и локальный адрес TLS-соединения
+pub fn connect(
+  config: Arc<ClientConfig>,
+  server: SocketAddr,
+) -> io::Result<(SocketAddr, SocketAddr)> {
+  let mut stream = TcpStream::connect_timeout(&server, HANDSHAKE_TIMEOUT)?;
+  let local_addr = stream.local_addr()?;
+  let name = ServerName::IpAddress(server.ip().into());
+  let mut conn = ClientConnection::new(config, name).map_err(io::Error::other)?;
+  stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+  while conn.is_handshaking() {
+    conn.complete_io(&mut stream)?;
+  }
+  stream.set_read_timeout(None)?;
+
+  let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+  let inner = listener.local_addr()?;
+  thread::spawn(move || {
+    if let Ok((plain, _)) = listener.accept() {
+      tunnel(conn.into(), stream, plain);
+    }
+  });
+  Ok((inner, local_addr))
+}
+
+/// Гоняет данные между TLS-соединением и обычным, пока одно из них не закроется
+fn tunnel(conn: Connection, tls: TcpStream, plain: TcpStream) {
+  let _ = tls.set_nodelay(true);
+  let _ = plain.set_nodelay(true);
+  let conn = Mutex::new(conn);
+  thread::scope(|s| {
+    s.spawn(|| {
+      let _ = encrypt(&conn, &tls, &plain);
+      let _ = tls.shutdown(Shutdown::Both);
+      let _ = plain.shutdown(Shutdown::Both);
+    });
+    let _ = decrypt(&conn, &tls, &plain);
+    let _ = tls.shutdown(Shutdown::Both);
+    let _ = plain.shutdown(Shutdown::Both);
+  });
+}
+
+fn decrypt(conn: &Mutex<Connection>, mut tls: &TcpStream, mut plain: &TcpStream) -> io::Result<()> {
+  let mut buf = [0; 16 * 1024];
+  let mut plaintext = Vec::new();
+  // Рукопожатие могло прочитать и первые данные
+  let mut closed = {
+    let mut conn = conn.lock().unwrap();
+    flush(&mut conn, tls)?;
+    drain(&mut conn, &mut plaintext)?
+  };
+  loop {
+    plain.write_all(&plaintext)?;
+    plaintext.clear();
+    if closed {
+      return Ok(());
+    }
+    let n = tls.read(&mut buf)?;
+    if n == 0 {
+      return Ok(());
+    }
+    let mut data = &buf[..n];
+    while !data.is_empty() && !closed {
+      let mut conn = conn.lock().unwrap();
+      conn.read_tls(&mut data)?;
+      let state = conn.process_new_packets();
+      flush(&mut conn, tls)?;
+      state.map_err(io::Error::other)?;
+      closed = drain(&mut conn, &mut plaintext)?;
+    }
+  }
+}
+
+/// Забирает расшифрованные данные. `true`, если собеседник закрыл соединение
+fn drain(conn: &mut Connection, plaintext: &mut Vec<u8>) -> io::Result<bool> {
+  match conn.reader().read_to_end(plaintext) {
+    Ok(_) => Ok(true),
+    Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(false),
+    Err(err) => Err(err),
+  }
+}
+
+fn encrypt(conn: &Mutex<Connection>, tls: &TcpStream, mut plain: &TcpStream) -> io::Result<()> {
+  let mut buf = [0; 16 * 1024];
+  loop {
+    let n = plain.read(&mut buf)?;
+    let mut conn = conn.lock().unwrap();
+    if n == 0 {
+      conn.send_close_notify();
+      return flush(&mut conn, tls);
+    }
+    conn.writer().write_all(&buf[..n])?;
+    flush(&mut conn, tls)?;
+  }
+}
+
+fn flush(conn: &mut Connection, mut tls: &TcpStream) -> io::Result<()> {
+  while conn.wants_write() {
+    conn.write_tls(&mut tls)?;
+  }
+  Ok(())
+}